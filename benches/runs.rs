@@ -0,0 +1,78 @@
+// Compares drawing long lines into a frame buffer pixel by pixel with
+// `BresenhamIter` against filling whole runs from `LineRunIter`.
+//
+// Run with `cargo bench --bench runs`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use line_rs::bresenham::{BresenhamIter, Point};
+use line_rs::runs::{LineRun, LineRunIter};
+
+const WIDTH: usize = 8192;
+const HEIGHT: usize = 512;
+
+fn time<F: FnMut()>(name: &str, rounds: u32, mut f: F) -> Duration {
+  f();
+  let start = Instant::now();
+  for _ in 0..rounds {
+    f();
+  }
+  let per_round = start.elapsed() / rounds;
+  println!("{:<32} {:>12?} per round", name, per_round);
+  per_round
+}
+
+fn draw_points(buffer: &mut [u8], lines: &[(Point<i32>, Point<i32>)]) {
+  for &(p1, p2) in lines {
+    for p in BresenhamIter::new(p1, p2) {
+      buffer[p.y as usize * WIDTH + p.x as usize] = 1;
+    }
+  }
+}
+
+fn draw_runs(buffer: &mut [u8], lines: &[(Point<i32>, Point<i32>)]) {
+  for &(p1, p2) in lines {
+    for run in LineRunIter::new(p1, p2) {
+      match run {
+        LineRun::Horizontal { y, x_start, x_end } => {
+          let (lo, hi) = (x_start.min(x_end), x_start.max(x_end));
+          let row = y as usize * WIDTH;
+          buffer[row + lo as usize..=row + hi as usize].fill(1);
+        },
+        LineRun::Vertical { x, y_start, y_end } => {
+          for y in y_start.min(y_end)..=y_start.max(y_end) {
+            buffer[y as usize * WIDTH + x as usize] = 1;
+          }
+        },
+      }
+    }
+  }
+}
+
+fn main() {
+  let (w, h) = (WIDTH as i32 - 1, HEIGHT as i32 - 1);
+  // long lines across the buffer, from flat to a slope of about 1 in 80,
+  // and 45 degree lines, where every run is a single pixel and run-slice
+  // has nothing to gain
+  let shallow: Vec<_> = (0..64).map(|i| (Point::new(0, i), Point::new(w, h * i / 1024 + i))).collect();
+  let diagonal: Vec<_> = (0..64).map(|i| (Point::new(i, 0), Point::new(h + i, h))).collect();
+
+  let mut buffer = vec![0u8; WIDTH * HEIGHT];
+  for (name, lines) in &[("shallow", &shallow), ("diagonal", &diagonal)] {
+    let points = time(&format!("{} lines, pixel by pixel", name), 200, || {
+      draw_points(black_box(&mut buffer), black_box(lines))
+    });
+    let runs = time(&format!("{} lines, run-slice", name), 200, || {
+      draw_runs(black_box(&mut buffer), black_box(lines))
+    });
+    println!("{:<32} {:>11.2}x", "speedup", points.as_secs_f64() / runs.as_secs_f64());
+  }
+
+  // both draw the same pixels
+  let mut by_runs = vec![0u8; WIDTH * HEIGHT];
+  buffer.iter_mut().for_each(|b| *b = 0);
+  draw_points(&mut buffer, &shallow);
+  draw_runs(&mut by_runs, &shallow);
+  assert!(buffer == by_runs);
+}
@@ -0,0 +1,574 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{LineRSInt, LineRSWide, Point, Rect};
+use crate::ellipse::EllipseShape;
+
+// walks the outline of an ellipse in order of increasing angle (from the x
+// axis towards the y axis), starting on the x axis, yielding each pixel
+// once as doubled offsets (u, v) from the centre.
+//
+// the outline is the same in all four quadrants up to reflection, so each
+// quadrant walks the same staircase in (|u|, |v|): rows of increasing |v|,
+// each from its rightmost pixel in to just past the end of the next row out
+// (or in to the axis, on the outermost row). even quadrants walk it forwards
+// and odd ones backwards. the pixels on the axes go to the quadrant they
+// start, and the centre, which is only on the outline of an ellipse
+// flattened into a line, to the first.
+#[derive(Debug, Clone)]
+pub(crate) struct OutlineLoop<T: LineRSInt> {
+  shape: EllipseShape<T>,
+  quadrant: u8,
+  v: T::Wide,
+  // the next u to yield in row v, and the last
+  u: Option<T::Wide>,
+  row_end: T::Wide,
+  row_entered: bool,
+}
+
+impl<T: LineRSInt> OutlineLoop<T> {
+  pub(crate) fn new(shape: EllipseShape<T>) -> OutlineLoop<T> {
+    let mut walk = OutlineLoop {
+      shape,
+      quadrant: 0,
+      v: T::Wide::from(0),
+      u: None,
+      row_end: T::Wide::from(0),
+      row_entered: false,
+    };
+    walk.v = walk.first_row();
+    walk
+  }
+
+  fn forwards(&self) -> bool {
+    self.quadrant & 1 == 0
+  }
+
+  // the smallest |v| of the quadrant's rows. rows on the u axis belong to
+  // the even quadrants.
+  fn innermost_row(&self) -> T::Wide {
+    let two = T::Wide::from(2);
+    if self.shape.h % two != T::Wide::from(0) {
+      T::Wide::from(1)
+    } else if self.forwards() {
+      T::Wide::from(0)
+    } else {
+      two
+    }
+  }
+
+  fn first_row(&self) -> T::Wide {
+    if self.forwards() { self.innermost_row() } else { self.shape.h }
+  }
+
+  // the smallest |u| in row v. columns on the v axis belong to the odd
+  // quadrants, apart from the centre.
+  fn innermost_column(&self, v: T::Wide) -> T::Wide {
+    let zero = T::Wide::from(0);
+    let two = T::Wide::from(2);
+    if self.shape.w % two != zero {
+      T::Wide::from(1)
+    } else if !self.forwards() || (self.quadrant == 0 && v == zero) {
+      zero
+    } else {
+      two
+    }
+  }
+
+  // the |u| range of row v in this quadrant
+  fn row(&self, v: T::Wide) -> (T::Wide, T::Wide) {
+    let two = T::Wide::from(2);
+    let outer = self.shape.reach(v);
+    let innermost = self.innermost_column(v);
+    let inner = if v == self.shape.h {
+      innermost
+    } else {
+      (self.shape.reach(v + two) + two).min(outer).max(innermost)
+    };
+    (inner, outer)
+  }
+}
+
+impl<T: LineRSInt> Iterator for OutlineLoop<T> {
+  type Item = (T::Wide, T::Wide);
+
+  fn next(&mut self) -> Option<(T::Wide, T::Wide)> {
+    let two = T::Wide::from(2);
+    loop {
+      if self.quadrant >= 4 {
+        return None;
+      }
+      if let Some(u) = self.u {
+        let step = if self.forwards() { -two } else { two };
+        self.u = if u == self.row_end { None } else { Some(u + step) };
+        let (u, v) = match self.quadrant {
+          0 => (u, self.v),
+          1 => (-u, self.v),
+          2 => (-u, -self.v),
+          _ => (u, -self.v),
+        };
+        return Some((u, v));
+      }
+
+      if self.row_entered {
+        self.row_entered = false;
+        let next = if self.forwards() { self.v + two } else { self.v - two };
+        if next < self.innermost_row() || next > self.shape.h {
+          self.quadrant += 1;
+          self.v = self.first_row();
+        } else {
+          self.v = next;
+        }
+        continue;
+      }
+
+      self.row_entered = true;
+      if self.innermost_row() > self.shape.h {
+        continue;
+      }
+      let (inner, outer) = self.row(self.v);
+      if inner <= outer {
+        let (first, last) = if self.forwards() { (outer, inner) } else { (inner, outer) };
+        self.u = Some(first);
+        self.row_end = last;
+      }
+    }
+  }
+}
+
+// which way an end of an arc lies from the centre
+#[derive(Debug, Copy, Clone)]
+enum Direction<W> {
+  // doubled offsets from the centre
+  Towards(W, W),
+  // the cosine and sine of an angle
+  #[cfg_attr(not(feature = "std"), allow(dead_code))]
+  Angle(f64, f64),
+}
+
+// 0 for angles in [0, pi), 1 for [pi, 2pi)
+fn half<W: LineRSWide>(u: W, v: W) -> u8 {
+  let zero = W::from(0);
+  if v > zero || (v == zero && u >= zero) { 0 } else { 1 }
+}
+
+// how the angle of the offset (u, v) compares with that of `direction`
+fn compare<W: LineRSWide>(u: W, v: W, direction: Direction<W>) -> Ordering {
+  match direction {
+    Direction::Towards(x, y) => {
+      half(u, v).cmp(&half(x, y)).then_with(|| compare_products(v, x, u, y))
+    },
+    Direction::Angle(cos, sin) => {
+      let direction_half = if sin > 0.0 || (sin == 0.0 && cos >= 0.0) { 0 } else { 1 };
+      half(u, v).cmp(&direction_half).then_with(|| {
+        let (u, v) = (u.line_rs_to_f64(), v.line_rs_to_f64());
+        let cross = u * sin - v * cos;
+        // angles like pi / 2 don't come out exact, so pixels lying within
+        // rounding error of the direction count as lying on it
+        if cross.abs() <= 1e-12 * (u.abs() + v.abs()) {
+          Ordering::Equal
+        } else if cross > 0.0 {
+          Ordering::Less
+        } else {
+          Ordering::Greater
+        }
+      })
+    },
+  }
+}
+
+// how a * b compares with c * d, even when they don't fit in W
+fn compare_products<W: LineRSWide>(a: W, b: W, c: W, d: W) -> Ordering {
+  let zero = W::from(0);
+  if let (Some(ab), Some(cd)) = (a.line_rs_checked_mul(b), c.line_rs_checked_mul(d)) {
+    return ab.cmp(&cd);
+  }
+  let sign = |v: W| v.cmp(&zero);
+  let (left, right) = (sign_product(sign(a), sign(b)), sign_product(sign(c), sign(d)));
+  if left != right || left == Ordering::Equal {
+    return left.cmp(&right);
+  }
+  // the same sign, so it comes down to |a| / |c| against |d| / |b|. those
+  // are taken negative, which unlike positive always fits
+  let negative = |v: W| if v > zero { zero - v } else { v };
+  let magnitudes = compare_fractions(negative(a), negative(c), negative(d), negative(b));
+  if left == Ordering::Greater { magnitudes } else { magnitudes.reverse() }
+}
+
+fn sign_product(a: Ordering, b: Ordering) -> Ordering {
+  match (a, b) {
+    (Ordering::Equal, _) | (_, Ordering::Equal) => Ordering::Equal,
+    _ if a == b => Ordering::Greater,
+    _ => Ordering::Less,
+  }
+}
+
+// how p1 / q1 compares with p2 / q2, for p1 and p2 at most zero and q1 and
+// q2 below it, by comparing their continued fractions term by term
+fn compare_fractions<W: LineRSWide>(mut p1: W, mut q1: W, mut p2: W, mut q2: W) -> Ordering {
+  let zero = W::from(0);
+  // one less than the whole part, and what's left over, which unlike the
+  // whole part can't overflow
+  let split = |p: W, q: W| if p <= q { ((p - q) / q, (p - q) % q) } else { (zero - W::from(1), p) };
+  loop {
+    let ((whole1, rest1), (whole2, rest2)) = (split(p1, q1), split(p2, q2));
+    if whole1 != whole2 {
+      return whole1.cmp(&whole2);
+    }
+    if rest1 == zero || rest2 == zero {
+      // a remainder closer to zero is a smaller fraction
+      return rest2.cmp(&rest1);
+    }
+    // rest1 / q1 against rest2 / q2 is q2 / rest2 against q1 / rest1
+    let next = (q2, rest2, q1, rest1);
+    p1 = next.0;
+    q1 = next.1;
+    p2 = next.2;
+    q2 = next.3;
+  }
+}
+
+/// Lazily yields part of the outline of a circle or ellipse as a connected
+/// path, in order of increasing angle from the start of the arc to its end.
+///
+/// Angles run from the x axis towards the y axis, as in
+/// `RotatedEllipseSpanIter`. The pixels are those of `CircleIter` for a
+/// circle and of `EllipseIter` for an ellipse, and an arc includes every
+/// one whose centre lies in the range of angles, including at either end.
+/// The arithmetic is done in `LineRSInt::Wide`, with the same limits as the
+/// whole shape, and pixels outside the range of `T` are skipped. The offsets
+/// of `start` and `end` from the centre, doubled when the centre falls
+/// between pixels, must fit there too, or there is no arc; their directions
+/// are compared exactly however far away they are.
+#[derive(Debug, Clone)]
+pub struct ArcIter<T: LineRSInt> {
+  shape: EllipseShape<T>,
+  walk: OutlineLoop<T>,
+  start: Direction<T::Wide>,
+  end: Direction<T::Wide>,
+  // whether the arc passes angle zero, where the outline starts, and so
+  // needs a second lap; a full arc goes round until it reaches `start`
+  wraps: bool,
+  full: bool,
+  second_lap: bool,
+  done: bool,
+}
+
+impl<T: LineRSInt> ArcIter<T> {
+  /// The arc of the circle of `radius` about `center` running from the
+  /// direction of `start` round to the direction of `end`, as seen from
+  /// `center`. The two points needn't be on the circle. When they are in
+  /// the same direction the arc is the whole circle, starting there.
+  pub fn new(center: Point<T>, radius: u32, start: Point<T>, end: Point<T>) -> ArcIter<T> {
+    ArcIter::between_points(EllipseShape::circle(center, radius), start, end)
+  }
+
+  /// Like `new`, for the ellipse of `EllipseIter::new(rect)`.
+  pub fn elliptical(rect: Rect<T>, start: Point<T>, end: Point<T>) -> ArcIter<T> {
+    ArcIter::between_points(EllipseShape::inscribed(rect), start, end)
+  }
+
+  /// The arc of the circle of `radius` about `center` from `start_angle` to
+  /// `end_angle`, in radians. The end is taken to be less than a whole turn
+  /// past the start, unless it is at least a whole turn past, which gives
+  /// the whole circle.
+  #[cfg(feature = "std")]
+  pub fn from_angles(center: Point<T>, radius: u32, start_angle: f64, end_angle: f64) -> ArcIter<T> {
+    ArcIter::between_angles(EllipseShape::circle(center, radius), start_angle, end_angle)
+  }
+
+  /// Like `from_angles`, for the ellipse of `EllipseIter::new(rect)`.
+  #[cfg(feature = "std")]
+  pub fn elliptical_from_angles(rect: Rect<T>, start_angle: f64, end_angle: f64) -> ArcIter<T> {
+    ArcIter::between_angles(EllipseShape::inscribed(rect), start_angle, end_angle)
+  }
+
+  // a shape or end out of range of Wide gives no arc
+  fn between_points(shape: Option<EllipseShape<T>>, start: Point<T>, end: Point<T>) -> ArcIter<T> {
+    let zero = T::Wide::from(0);
+    let two = T::Wide::from(2);
+    let towards = |shape: &EllipseShape<T>, p: Point<T>| {
+      let dx = p.x.line_rs_widen()?.line_rs_checked_sub(shape.x0)?;
+      let dy = p.y.line_rs_widen()?.line_rs_checked_sub(shape.y0)?;
+      // only the direction of the doubled offset from the centre matters,
+      // so it is halved when that can be done exactly
+      let (u, v) = if shape.cx2 % two == zero && shape.cy2 % two == zero {
+        (dx.line_rs_checked_sub(shape.cx2 / two)?, dy.line_rs_checked_sub(shape.cy2 / two)?)
+      } else {
+        let u = two.line_rs_checked_mul(dx)?.line_rs_checked_sub(shape.cx2)?;
+        (u, two.line_rs_checked_mul(dy)?.line_rs_checked_sub(shape.cy2)?)
+      };
+      // the centre itself points along the x axis
+      Some(if u == zero && v == zero { (T::Wide::from(1), zero) } else { (u, v) })
+    };
+    let ends = shape.and_then(|shape| Some((towards(&shape, start)?, towards(&shape, end)?, shape)));
+    let ((start_u, start_v), (end_u, end_v), shape) = match ends {
+      Some(ends) => ends,
+      None => return ArcIter::none(),
+    };
+    let end = Direction::Towards(end_u, end_v);
+    let order = compare(start_u, start_v, end);
+    ArcIter::with_ends(
+      shape,
+      Direction::Towards(start_u, start_v),
+      end,
+      order == Ordering::Greater,
+      order == Ordering::Equal,
+    )
+  }
+
+  #[cfg(feature = "std")]
+  fn between_angles(shape: Option<EllipseShape<T>>, start_angle: f64, end_angle: f64) -> ArcIter<T> {
+    let shape = match shape {
+      Some(shape) => shape,
+      None => return ArcIter::none(),
+    };
+    let turn = 2.0 * core::f64::consts::PI;
+    let direction = |angle: f64| {
+      // sin(pi) and the like come out a rounding error away from zero,
+      // which would put the direction on the wrong side of the axis
+      let snap = |v: f64| if v.abs() < 1e-12 { 0.0 } else { v };
+      let (sin, cos) = angle.sin_cos();
+      Direction::Angle(snap(cos), snap(sin))
+    };
+    ArcIter::with_ends(
+      shape,
+      direction(start_angle),
+      direction(end_angle),
+      end_angle.rem_euclid(turn) < start_angle.rem_euclid(turn),
+      end_angle - start_angle >= turn,
+    )
+  }
+
+  fn none() -> ArcIter<T> {
+    let along_x = Direction::Towards(T::Wide::from(1), T::Wide::from(0));
+    let mut arc = ArcIter::with_ends(EllipseShape::point(), along_x, along_x, false, false);
+    arc.done = true;
+    arc
+  }
+
+  fn with_ends(
+    shape: EllipseShape<T>,
+    start: Direction<T::Wide>,
+    end: Direction<T::Wide>,
+    wraps: bool,
+    full: bool,
+  ) -> ArcIter<T> {
+    ArcIter {
+      walk: OutlineLoop::new(shape.clone()),
+      shape,
+      start,
+      end,
+      wraps: wraps || full,
+      full,
+      second_lap: false,
+      done: false,
+    }
+  }
+}
+
+impl<T: LineRSInt> Iterator for ArcIter<T> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    let two = T::Wide::from(2);
+    while !self.done {
+      let (u, v) = match self.walk.next() {
+        Some(offset) => offset,
+        None if self.wraps && !self.second_lap => {
+          self.second_lap = true;
+          self.walk = OutlineLoop::new(self.shape.clone());
+          continue;
+        },
+        None => {
+          self.done = true;
+          break;
+        },
+      };
+
+      // the outline is walked in order of angle, so the arc is one run of it
+      let past_end = if !self.second_lap {
+        if compare(u, v, self.start) == Ordering::Less {
+          continue;
+        }
+        !self.wraps && compare(u, v, self.end) == Ordering::Greater
+      } else if self.full {
+        compare(u, v, self.start) != Ordering::Less
+      } else {
+        compare(u, v, self.end) == Ordering::Greater
+      };
+      if past_end {
+        self.done = true;
+        break;
+      }
+
+      let x = self.shape.x0.line_rs_checked_add((self.shape.cx2 + u) / two).and_then(T::line_rs_narrow);
+      let y = self.shape.y0.line_rs_checked_add((self.shape.cy2 + v) / two).and_then(T::line_rs_narrow);
+      if let (Some(x), Some(y)) = (x, y) {
+        return Some(Point { x, y });
+      }
+    }
+    None
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for ArcIter<T> {}
+
+/// All the pixels of `ArcIter::new(center, radius, start, end)`, in order.
+#[cfg(feature = "alloc")]
+pub fn calculate_arc<T: LineRSInt>(
+  center: Point<T>,
+  radius: u32,
+  start: Point<T>,
+  end: Point<T>,
+) -> Vec<Point<T>> {
+  ArcIter::new(center, radius, start, end).collect()
+}
+
+/// All the pixels of `ArcIter::from_angles(center, radius, start_angle, end_angle)`,
+/// in order.
+#[cfg(feature = "std")]
+pub fn calculate_arc_from_angles<T: LineRSInt>(
+  center: Point<T>,
+  radius: u32,
+  start_angle: f64,
+  end_angle: f64,
+) -> Vec<Point<T>> {
+  ArcIter::from_angles(center, radius, start_angle, end_angle).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::calculate_arc;
+  use super::ArcIter;
+  use crate::bresenham::{Point, Rect};
+  use crate::circle::calculate_circle;
+  use crate::ellipse::calculate_ellipse;
+  use crate::test_util::sorted;
+
+  fn connected(path: &[Point<i32>]) -> bool {
+    path.windows(2).all(|pair| {
+      let (dx, dy) = ((pair[1].x - pair[0].x).abs(), (pair[1].y - pair[0].y).abs());
+      dx <= 1 && dy <= 1 && dx + dy > 0
+    })
+  }
+
+  #[test]
+  fn quarter_and_wrapping_arcs() {
+    let quarter = calculate_arc(Point::new(0, 0), 3, Point::new(5, 0), Point::new(0, 5));
+    let expected = vec![
+      Point::new(3, 0),
+      Point::new(3, 1),
+      Point::new(2, 2),
+      Point::new(1, 3),
+      Point::new(0, 3),
+    ];
+    assert_eq!(quarter, expected);
+
+    // through angle zero, from straight up to straight down
+    let right = calculate_arc(Point::new(10, 10), 4, Point::new(10, 0), Point::new(10, 11));
+    assert_eq!(right.first(), Some(&Point::new(10, 6)));
+    assert_eq!(right.last(), Some(&Point::new(10, 14)));
+    assert!(connected(&right));
+    assert!(right.iter().all(|p| p.x >= 10));
+    assert_eq!(right.len(), (calculate_circle(Point::new(10, 10), 4).len() + 2) / 2);
+  }
+
+  #[test]
+  fn whole_outlines_in_order() {
+    for radius in 0..30 {
+      let center = Point::new(-4, 7);
+      let start = Point::new(0, 9);
+      let full = calculate_arc(center, radius, start, start);
+      assert_eq!(sorted(&full), sorted(&calculate_circle(center, radius)), "radius {}", radius);
+      assert!(connected(&full));
+    }
+    for w in 0..14 {
+      for h in 0..14 {
+        let rect = Rect::new(Point::new(-3, 2), Point::new(-3 + w, 2 + h));
+        let full: Vec<_> = ArcIter::elliptical(rect, Point::new(0, 0), Point::new(0, 0)).collect();
+        assert_eq!(sorted(&full), sorted(&calculate_ellipse(rect)), "{}x{}", w, h);
+        if w > 0 && h > 0 {
+          assert!(connected(&full), "{}x{}", w, h);
+        }
+      }
+    }
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn from_angles() {
+    use core::f64::consts::PI;
+    use super::calculate_arc_from_angles;
+
+    let center = Point::new(2, 2);
+    assert_eq!(
+      calculate_arc_from_angles(center, 7, 0.0, PI / 2.0),
+      calculate_arc(center, 7, Point::new(3, 2), Point::new(2, 3))
+    );
+    assert_eq!(
+      calculate_arc_from_angles(center, 7, -PI / 2.0, PI),
+      calculate_arc(center, 7, Point::new(2, 0), Point::new(0, 2))
+    );
+    let full = calculate_arc_from_angles(center, 7, 1.0, 1.0 + 2.0 * PI);
+    assert_eq!(sorted(&full), sorted(&calculate_circle(center, 7)));
+  }
+
+  #[test]
+  fn products_compared_exactly() {
+    use super::compare_products;
+    let values = [i64::MIN, i64::MIN + 1, -(1 << 40) - 1, -3, -1, 0, 1, 2, 1 << 40, i64::MAX - 1, i64::MAX];
+    for &a in &values {
+      for &b in &values {
+        for &(c, d) in &[(a, b), (b, a), (a, i64::MAX), (i64::MIN, b), (a + (a < 0) as i64, b)] {
+          let wide = (i128::from(a) * i128::from(b)).cmp(&(i128::from(c) * i128::from(d)));
+          assert_eq!(compare_products(a, b, c, d), wide, "{} {} {} {}", a, b, c, d);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn far_ends_at_the_limits_of_wide() {
+    let (max, min) = (i128::MAX, i128::MIN);
+    let center = Point::new(0i128, 0);
+    let arc = |start: (i128, i128), end: (i128, i128)| {
+      calculate_arc(center, 3, Point::new(start.0, start.1), Point::new(end.0, end.1))
+    };
+    assert_eq!(arc((max, 0), (0, 5)), arc((5, 0), (0, 5)));
+    assert_eq!(arc((1, 0), (max, max)), arc((1, 0), (1, 1)));
+    assert_eq!(arc((1, 0), (max, max - 1)), arc((1, 0), (1000, 999)));
+    assert_ne!(arc((1, 0), (max, max - 1)), arc((1, 0), (1, 1)));
+    assert_eq!(arc((max - 1, max), (min, 0)), arc((999, 1000), (-1, 0)));
+    assert_eq!(arc((min, -1), (min + 1, min)), arc((-1000, -1), (-1, -1)));
+    // ends and centres each within Wide but too far apart
+    let far = Point::new(max, 0);
+    assert_eq!(calculate_arc(Point::new(-1, 0), 3, far, Point::new(0, 5)), vec![]);
+    assert_eq!(calculate_arc(Point::new(max, 0), 3, Point::new(min, 0), Point::new(max, 5)), vec![]);
+    // an ellipse centred between pixels needs its ends' doubled offsets to fit
+    let rect = Rect::new(Point::new(0i128, 0), Point::new(5, 4));
+    let near = ArcIter::elliptical(rect, Point::new(5, 2), Point::new(0, 4)).collect::<Vec<_>>();
+    assert_eq!(ArcIter::elliptical(rect, Point::new(max / 2, 2), Point::new(0, 4)).collect::<Vec<_>>(), near);
+    assert_eq!(ArcIter::elliptical(rect, far, Point::new(0, 4)).count(), 0);
+    // and the ends of u128 don't fit at all
+    let (center, far) = (Point::new(10u128, 10), Point::new(u128::MAX, 10));
+    assert_eq!(calculate_arc(center, 3, far, Point::new(10, 20)), vec![]);
+    let within = Point::new(i128::MAX as u128, 10);
+    let near = calculate_arc(center, 3, Point::new(20, 10), Point::new(10, 20));
+    assert_eq!(calculate_arc(center, 3, within, Point::new(10, 20)), near);
+  }
+
+  #[test]
+  fn centres_at_the_limits_of_wide() {
+    let (max, min) = (i128::MAX, i128::MIN);
+    for &(center, toward) in &[((max - 1, 0), (max, 0)), ((min + 1, min + 1), (min, min + 1))] {
+      let center = Point::new(center.0, center.1);
+      let toward = Point::new(toward.0, toward.1);
+      let full = calculate_arc(center, 5, toward, toward);
+      assert_eq!(sorted(&full), sorted(&calculate_circle(center, 5)));
+    }
+    assert_eq!(calculate_arc(Point::new(u128::MAX, 10), 3, Point::new(0, 0), Point::new(10, 20)), vec![]);
+  }
+}
@@ -0,0 +1,377 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{add_quotients, floor_div, LineRSInt, LineRSWide, Point};
+
+/// Lazily yields the pixels of a quadratic or cubic Bézier curve as one
+/// connected path from the first control point to the last.
+///
+/// The curve is sampled at evenly spaced parameter steps, close enough
+/// together that consecutive samples are at most a pixel apart on each
+/// axis, and each sample is rounded to its nearest pixel (halves round up).
+/// Repeated pixels are dropped, and so are corner pixels the path can step
+/// diagonally past, so consecutive pixels are always 8-connected neighbours
+/// and the path is one pixel thin. Every pixel is within half a pixel of
+/// the curve on each axis, and both end control points are always drawn.
+///
+/// The samples are exact, but no polynomial is evaluated for them: each
+/// coordinate and its forward differences are kept as whole pixels plus an
+/// error term, like the error term of a line, so a step costs a few
+/// additions and comparisons.
+///
+/// The curve never leaves the hull of its control points, so every pixel is
+/// in range of `T`. The arithmetic is done in `LineRSInt::Wide` relative to
+/// the first control point: a quadratic curve needs 16 times the square of
+/// the largest coordinate difference between consecutive control points to
+/// fit there, and a cubic curve 128 times its cube. A curve whose
+/// arithmetic doesn't fit there draws nothing.
+#[derive(Debug, Clone)]
+pub struct BezierIter<T: LineRSInt> {
+  start: Point<T>,
+  origin: (T::Wide, T::Wide),
+  // for each axis, twice the offset from the origin times steps^degree,
+  // plus steps^degree to round it, and its first three forward
+  // differences. each is kept as a quotient and remainder of `divisor`,
+  // twice steps^degree, so the quotient of the first is the sample's pixel.
+  x: [(T::Wide, T::Wide); 4],
+  y: [(T::Wide, T::Wide); 4],
+  divisor: T::Wide,
+  step: T::Wide,
+  steps: T::Wide,
+  last: Option<Point<T>>,
+  // the newest pixel, held back until it is known whether the path can
+  // step diagonally past it
+  pending: Option<Point<T>>,
+}
+
+impl<T: LineRSInt> BezierIter<T> {
+  /// The quadratic curve from `p0` to `p2` pulled towards `p1`.
+  pub fn quadratic(p0: Point<T>, p1: Point<T>, p2: Point<T>) -> BezierIter<T> {
+    BezierIter::with_controls(p0, &[p1, p2])
+  }
+
+  /// The cubic curve from `p0` to `p3` leaving towards `p1` and arriving
+  /// from the direction of `p2`.
+  pub fn cubic(p0: Point<T>, p1: Point<T>, p2: Point<T>, p3: Point<T>) -> BezierIter<T> {
+    BezierIter::with_controls(p0, &[p1, p2, p3])
+  }
+
+  fn with_controls(start: Point<T>, rest: &[Point<T>]) -> BezierIter<T> {
+    BezierIter::walking(start, rest).unwrap_or_else(|| {
+      // with a control point out of range of Wide, or a curve too big for
+      // it, there are no steps
+      let zero = T::Wide::from(0);
+      BezierIter {
+        start,
+        origin: (zero, zero),
+        x: [(zero, zero); 4],
+        y: [(zero, zero); 4],
+        divisor: T::Wide::from(1),
+        step: zero,
+        steps: -T::Wide::from(1),
+        last: None,
+        pending: None,
+      }
+    })
+  }
+
+  fn walking(start: Point<T>, rest: &[Point<T>]) -> Option<BezierIter<T>> {
+    let zero = T::Wide::from(0);
+    let one = T::Wide::from(1);
+    let two = T::Wide::from(2);
+    let add = |a: T::Wide, b: T::Wide| a.line_rs_checked_add(b);
+    let sub = |a: T::Wide, b: T::Wide| a.line_rs_checked_sub(b);
+    let mul = |a: T::Wide, b: T::Wide| a.line_rs_checked_mul(b);
+    let abs = |v: T::Wide| if v < zero { sub(zero, v) } else { Some(v) };
+    let widen = |p: Point<T>| Some((p.x.line_rs_widen()?, p.y.line_rs_widen()?));
+    let (x0, y0) = widen(start)?;
+    let mut controls = [(zero, zero); 3];
+    // the derivative along each axis is at most the degree times the
+    // longest leg of the control polygon, so that many steps per unit of
+    // leg keep samples within a pixel of each other
+    let mut longest_leg = zero;
+    let mut previous = (zero, zero);
+    for (control, &p) in controls.iter_mut().zip(rest) {
+      let (x, y) = widen(p)?;
+      *control = (sub(x, x0)?, sub(y, y0)?);
+      let leg = abs(sub(control.0, previous.0)?)?.max(abs(sub(control.1, previous.1)?)?);
+      longest_leg = longest_leg.max(leg);
+      previous = *control;
+    }
+    let cubic = rest.len() == 3;
+    let n = mul(T::Wide::from(rest.len() as u32), longest_leg)?;
+    let scale = if n == zero { one } else if cubic { mul(mul(n, n)?, n)? } else { mul(n, n)? };
+    let divisor = mul(two, scale)?;
+
+    // the curve in powers of the parameter t is a1 t + a2 t^2 + a3 t^3, so
+    // times n^degree at t = k / n it is a polynomial in k whose forward
+    // differences at k = 0 follow directly
+    let differences = |c1: T::Wide, c2: T::Wide, c3: T::Wide| {
+      let three = T::Wide::from(3);
+      let deltas = if cubic {
+        let a1 = mul(three, c1)?;
+        let a2 = mul(three, sub(c2, mul(two, c1)?)?)?;
+        let a3 = add(sub(c3, mul(three, c2)?)?, mul(three, c1)?)?;
+        let six_a3 = mul(T::Wide::from(6), a3)?;
+        let first = add(add(mul(mul(a1, n)?, n)?, mul(a2, n)?)?, a3)?;
+        [first, add(mul(mul(two, a2)?, n)?, six_a3)?, six_a3]
+      } else {
+        let (a1, a2) = (mul(two, c1)?, sub(c2, mul(two, c1)?)?);
+        [add(mul(a1, n)?, a2)?, mul(two, a2)?, zero]
+      };
+      let split = |v: T::Wide| {
+        let v = mul(two, v)?;
+        let q = floor_div(v, divisor);
+        Some((q, v - q * divisor))
+      };
+      let rounding = if n == zero { (zero, zero) } else { (zero, scale) };
+      Some([rounding, split(deltas[0])?, split(deltas[1])?, split(deltas[2])?])
+    };
+    let [c1, c2, c3] = controls;
+    Some(BezierIter {
+      start,
+      origin: (x0, y0),
+      x: differences(c1.0, c2.0, c3.0)?,
+      y: differences(c1.1, c2.1, c3.1)?,
+      divisor,
+      step: zero,
+      steps: n,
+      last: None,
+      pending: None,
+    })
+  }
+
+  // the nearest pixel to the curve at the current step, after which the
+  // walk moves on to the next
+  fn sample(&mut self) -> Point<T> {
+    let x = self.origin.0 + self.x[0].0;
+    let y = self.origin.1 + self.y[0].0;
+    if self.step < self.steps {
+      for axis in [&mut self.x, &mut self.y] {
+        for i in 0..3 {
+          axis[i] = add_quotients(axis[i], axis[i + 1], self.divisor, T::Wide::from(1));
+        }
+      }
+    }
+    self.step = self.step + T::Wide::from(1);
+    // rounding keeps the sample inside the hull of the control points
+    Point {
+      x: T::line_rs_narrow(x).unwrap_or(self.start.x),
+      y: T::line_rs_narrow(y).unwrap_or(self.start.y),
+    }
+  }
+}
+
+fn touching<T: LineRSInt>(a: Point<T>, b: Point<T>) -> bool {
+  let one = T::Magnitude::line_rs_one();
+  a.x.line_rs_abs_diff(b.x) <= one && a.y.line_rs_abs_diff(b.y) <= one
+}
+
+impl<T: LineRSInt> Iterator for BezierIter<T> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    while self.step <= self.steps {
+      let point = self.sample();
+      let last = match self.last {
+        None => {
+          self.last = Some(point);
+          return Some(point);
+        },
+        Some(last) => last,
+      };
+      match self.pending {
+        Some(pending) if pending == point => {},
+        None if last == point => {},
+        // the pending pixel can be skipped, as the path reaches this one
+        // straight from the last
+        None => self.pending = Some(point),
+        Some(_) if point != last && touching(last, point) => self.pending = Some(point),
+        Some(pending) => {
+          self.last = Some(pending);
+          self.pending = Some(point);
+          return Some(pending);
+        },
+      }
+    }
+    self.pending.take()
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for BezierIter<T> {}
+
+/// All the pixels of `BezierIter::quadratic(p0, p1, p2)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_quadratic_bezier<T: LineRSInt>(p0: Point<T>, p1: Point<T>, p2: Point<T>) -> Vec<Point<T>> {
+  BezierIter::quadratic(p0, p1, p2).collect()
+}
+
+/// All the pixels of `BezierIter::cubic(p0, p1, p2, p3)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_cubic_bezier<T: LineRSInt>(
+  p0: Point<T>,
+  p1: Point<T>,
+  p2: Point<T>,
+  p3: Point<T>,
+) -> Vec<Point<T>> {
+  BezierIter::cubic(p0, p1, p2, p3).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::{calculate_cubic_bezier, calculate_quadratic_bezier, BezierIter};
+  use crate::bresenham::Point;
+  use crate::test_util::sorted_distinct;
+
+  // checks the path is connected, starts and ends on the end control
+  // points and stays within half a pixel of the curve on each axis
+  fn check_path(path: &[Point<i32>], controls: &[(f64, f64)]) {
+    let (first, last) = (controls[0], controls[controls.len() - 1]);
+    assert_eq!(path[0], Point::new(first.0 as i32, first.1 as i32));
+    assert_eq!(path[path.len() - 1], Point::new(last.0 as i32, last.1 as i32));
+    for pair in path.windows(2) {
+      let (dx, dy) = ((pair[1].x - pair[0].x).abs(), (pair[1].y - pair[0].y).abs());
+      assert!(dx <= 1 && dy <= 1 && dx + dy >= 1, "{:?} in {:?}", pair, controls);
+    }
+
+    // de casteljau on a fine grid of parameters
+    let curve: Vec<(f64, f64)> = (0..=4000)
+      .map(|i| {
+        let t = i as f64 / 4000.0;
+        let mut points = controls.to_vec();
+        while points.len() > 1 {
+          points = points
+            .windows(2)
+            .map(|w| (w[0].0 + (w[1].0 - w[0].0) * t, w[0].1 + (w[1].1 - w[0].1) * t))
+            .collect();
+        }
+        points[0]
+      })
+      .collect();
+    for p in path {
+      let near = curve
+        .iter()
+        .map(|c| (c.0 - p.x as f64).abs().max((c.1 - p.y as f64).abs()))
+        .fold(f64::INFINITY, f64::min);
+      assert!(near <= 0.51, "{:?} is {} from {:?}", p, near, controls);
+    }
+  }
+
+  #[test]
+  fn quadratic_curves() {
+    let curve = calculate_quadratic_bezier(Point::new(0, 0), Point::new(3, 5), Point::new(6, 0));
+    let expected = vec![
+      Point::new(0, 0),
+      Point::new(1, 1),
+      Point::new(2, 2),
+      Point::new(3, 3),
+      Point::new(4, 2),
+      Point::new(5, 1),
+      Point::new(6, 0),
+    ];
+    assert_eq!(curve, expected);
+
+    let points = [(0, 0), (10, -3), (-6, 8), (17, 17), (3, 12), (-9, -2)];
+    for &a in &points {
+      for &b in &points {
+        for &c in &points {
+          let path = calculate_quadratic_bezier(Point::new(a.0, a.1), Point::new(b.0, b.1), Point::new(c.0, c.1));
+          let controls = [a, b, c].iter().map(|&(x, y)| (x as f64, y as f64)).collect::<Vec<_>>();
+          check_path(&path, &controls);
+        }
+      }
+    }
+
+    // straight curves with the middle control point halfway along are lines
+    let line = calculate_quadratic_bezier(Point::new(0, 0), Point::new(3, 0), Point::new(6, 0));
+    assert_eq!(line, (0..=6).map(|x| Point::new(x, 0)).collect::<Vec<_>>());
+    assert_eq!(calculate_quadratic_bezier(Point::new(5, 5), Point::new(5, 5), Point::new(5, 5)), vec![Point::new(5, 5)]);
+  }
+
+  #[test]
+  fn cubic_curves() {
+    let points = [(0, 0), (12, -5), (-7, 9), (20, 14), (4, 4)];
+    for &a in &points {
+      for &b in &points {
+        for &c in &points {
+          for &d in &points {
+            let path = calculate_cubic_bezier(
+              Point::new(a.0, a.1),
+              Point::new(b.0, b.1),
+              Point::new(c.0, c.1),
+              Point::new(d.0, d.1),
+            );
+            let controls = [a, b, c, d].iter().map(|&(x, y)| (x as f64, y as f64)).collect::<Vec<_>>();
+            check_path(&path, &controls);
+          }
+        }
+      }
+    }
+
+    // a gentle s-curve is one pixel thin and visits no pixel twice
+    let curve = calculate_cubic_bezier(Point::new(0i32, 0), Point::new(30, 0), Point::new(0, 20), Point::new(30, 20));
+    for triple in curve.windows(3) {
+      assert!((triple[2].x - triple[0].x).abs() > 1 || (triple[2].y - triple[0].y).abs() > 1);
+    }
+    sorted_distinct(&curve);
+  }
+
+  #[test]
+  fn full_range_without_overflow() {
+    let curve = calculate_cubic_bezier(
+      Point::new(u8::MIN, u8::MIN),
+      Point::new(u8::MAX, u8::MIN),
+      Point::new(u8::MIN, u8::MAX),
+      Point::new(u8::MAX, u8::MAX),
+    );
+    assert_eq!(curve[0], Point::new(0, 0));
+    assert_eq!(curve[curve.len() - 1], Point::new(255, 255));
+    let curve = calculate_quadratic_bezier(
+      Point::new(i16::MIN, i16::MIN),
+      Point::new(i16::MAX, i16::MIN),
+      Point::new(i16::MAX, i16::MAX),
+    );
+    assert_eq!(curve[curve.len() - 1], Point::new(i16::MAX, i16::MAX));
+    let curve = calculate_cubic_bezier(
+      Point::new(i16::MIN, i16::MIN),
+      Point::new(i16::MAX, i16::MIN),
+      Point::new(i16::MIN, i16::MAX),
+      Point::new(i16::MAX, i16::MAX),
+    );
+    assert_eq!(curve[0], Point::new(i16::MIN, i16::MIN));
+    assert_eq!(curve[curve.len() - 1], Point::new(i16::MAX, i16::MAX));
+
+    // the walk starts at once however long the curve, and 32-bit
+    // coordinates stay within the limits of i128
+    let mut curve = BezierIter::cubic(
+      Point::new(i32::MIN, i32::MIN),
+      Point::new(i32::MAX, i32::MIN),
+      Point::new(i32::MIN, i32::MAX),
+      Point::new(i32::MAX, i32::MAX),
+    );
+    assert_eq!(curve.next(), Some(Point::new(i32::MIN, i32::MIN)));
+    assert_eq!(curve.next(), Some(Point::new(i32::MIN + 1, i32::MIN)));
+  }
+
+  #[test]
+  fn limits_of_wide() {
+    // a cubic curve needs 128 times the cube of its longest leg to fit in
+    // i128, which 2^39 does and 2^42 doesn't
+    let cubic = |leg: i64| {
+      BezierIter::cubic(Point::new(0, 0), Point::new(leg, 0), Point::new(0, leg), Point::new(leg, leg))
+    };
+    assert_eq!(cubic(1 << 39).take(2).collect::<Vec<_>>(), vec![Point::new(0, 0), Point::new(1, 0)]);
+    assert_eq!(cubic(1 << 42).next(), None);
+    // and a quadratic curve 16 times its square
+    let quadratic = |leg: i64| BezierIter::quadratic(Point::new(-leg, 0), Point::new(0, 0), Point::new(0, leg));
+    let start = -(1 << 61);
+    assert_eq!(quadratic(1 << 61).take(2).collect::<Vec<_>>(), vec![Point::new(start, 0), Point::new(start + 1, 0)]);
+    assert_eq!(quadratic(i64::MAX).next(), None);
+    // u128 control points past i128::MAX don't fit in it at all
+    let (near, far) = (Point::new(0u128, 0), Point::new(u128::MAX, 5));
+    assert_eq!(calculate_quadratic_bezier(near, far, Point::new(9, 9)), vec![]);
+    assert_eq!(calculate_cubic_bezier(far, near, near, near), vec![]);
+    assert_eq!(calculate_quadratic_bezier(near, Point::new(4, 0), Point::new(8, 0)).len(), 9);
+  }
+}
@@ -1,3 +1,8 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+use core::ops::ControlFlow;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Point<T> {
   pub x: T,
@@ -10,15 +15,248 @@ impl<T> Point<T> {
   }
 }
 
-pub trait LineRSInt: Sized + Copy {
+/// A point in three dimensions, for lines through voxel grids.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Point3<T> {
+  pub x: T,
+  pub y: T,
+  pub z: T,
+}
+
+impl<T> Point3<T> {
+  pub fn new(x: T, y: T, z: T) -> Point3<T> {
+    Point3 { x, y, z }
+  }
+}
+
+/// A point with any number of coordinates, for lines through spaces of
+/// more than three dimensions.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PointN<T, const N: usize> {
+  pub coords: [T; N],
+}
+
+impl<T, const N: usize> PointN<T, N> {
+  pub fn new(coords: [T; N]) -> PointN<T, N> {
+    PointN { coords }
+  }
+}
+
+/// A horizontal run of pixels along row `y`, from `x_start` to `x_end`,
+/// both inclusive. The fills yield these rather than single points, and
+/// always with `x_start <= x_end`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Span<T> {
+  pub y: T,
+  pub x_start: T,
+  pub x_end: T,
+}
+
+impl<T> Span<T> {
+  pub fn new(y: T, x_start: T, x_end: T) -> Span<T> {
+    Span { y, x_start, x_end }
+  }
+}
+
+/// The integer types lines can be drawn with.
+///
+/// Implemented for every primitive integer. A coordinate newtype (say a
+/// `TileCoord(i32)`) only needs the arithmetic supertraits and these few
+/// methods, which can usually forward to the wrapped integer.
+pub trait LineRSInt:
+  Sized +
+  Copy +
+  core::fmt::Debug +
+  core::cmp::PartialOrd +
+  core::ops::Add<Output = Self> +
+  core::ops::Sub<Output = Self> +
+  core::ops::Mul<Output = Self>
+{
+  /// An unsigned type that can hold the distance between any two values of
+  /// `Self`, e.g. `u8` for `i8`. All error term arithmetic is done in it.
+  type Magnitude: LineRSUint;
+  /// A signed type for the products of coordinates that thick lines,
+  /// curves and fills need: `i128` for every primitive integer. Each of
+  /// those rasterizers documents how much room it needs there and draws
+  /// nothing at all past that, or from a coordinate `Wide` can't hold,
+  /// which only happens for `u128` values above `i128::MAX`.
+  type Wide: LineRSWide;
+
   fn line_rs_zero() -> Self;
   fn line_rs_one() -> Self;
   fn line_rs_two() -> Self;
+  /// `|self - other|`, without overflowing.
+  fn line_rs_abs_diff(self, other: Self) -> Self::Magnitude;
+  /// `self + magnitude`. Only called when the result is representable.
+  fn line_rs_add_magnitude(self, magnitude: Self::Magnitude) -> Self;
+  /// `self - magnitude`. Only called when the result is representable.
+  fn line_rs_sub_magnitude(self, magnitude: Self::Magnitude) -> Self;
+  /// `None` when `self` is outside the range of `Self::Wide`.
+  fn line_rs_widen(self) -> Option<Self::Wide>;
+  /// `None` when `wide` is outside the range of `Self`.
+  fn line_rs_narrow(wide: Self::Wide) -> Option<Self>;
+}
+
+/// The signed integers used as `LineRSInt::Wide`.
+pub trait LineRSWide:
+  Copy +
+  core::fmt::Debug +
+  Ord +
+  From<u32> +
+  core::ops::Add<Output = Self> +
+  core::ops::Sub<Output = Self> +
+  core::ops::Mul<Output = Self> +
+  core::ops::Div<Output = Self> +
+  core::ops::Rem<Output = Self> +
+  core::ops::Neg<Output = Self>
+{
+  /// The integer square root. Only called on non-negative values.
+  fn line_rs_isqrt(self) -> Self;
+  /// The nearest `f64`, for comparing against floating point angles.
+  fn line_rs_to_f64(self) -> f64;
+  /// `self + rhs`, or `None` on overflow.
+  fn line_rs_checked_add(self, rhs: Self) -> Option<Self>;
+  /// `self - rhs`, or `None` on overflow.
+  fn line_rs_checked_sub(self, rhs: Self) -> Option<Self>;
+  /// `self * rhs`, or `None` on overflow.
+  fn line_rs_checked_mul(self, rhs: Self) -> Option<Self>;
+  /// `self + rhs`, clamped to the range of `Self`.
+  fn line_rs_saturating_add(self, rhs: Self) -> Self;
+}
+
+macro_rules! line_rs_wide_known_numbers {
+  ($t:ty) => {
+    impl LineRSWide for $t {
+      fn line_rs_isqrt(self) -> Self {
+        self.isqrt()
+      }
+
+      fn line_rs_to_f64(self) -> f64 {
+        self as f64
+      }
+
+      fn line_rs_checked_add(self, rhs: Self) -> Option<Self> {
+        self.checked_add(rhs)
+      }
+
+      fn line_rs_checked_sub(self, rhs: Self) -> Option<Self> {
+        self.checked_sub(rhs)
+      }
+
+      fn line_rs_checked_mul(self, rhs: Self) -> Option<Self> {
+        self.checked_mul(rhs)
+      }
+
+      fn line_rs_saturating_add(self, rhs: Self) -> Self {
+        self.saturating_add(rhs)
+      }
+    }
+  };
+}
+
+line_rs_wide_known_numbers!(i64);
+line_rs_wide_known_numbers!(i128);
+
+// floor(n / d) for any non-zero d
+pub(crate) fn floor_div<W: LineRSWide>(n: W, d: W) -> W {
+  let zero = W::from(0);
+  let (n, d) = if d < zero { (-n, -d) } else { (n, d) };
+  let q = n / d;
+  if n % d < zero { q - W::from(1) } else { q }
+}
+
+// ceil(n / d) for any non-zero d
+pub(crate) fn ceil_div<W: LineRSWide>(n: W, d: W) -> W {
+  -floor_div(-n, d)
+}
+
+// the smallest s with s * s >= n, for non-negative n
+pub(crate) fn ceil_sqrt<W: LineRSWide>(n: W) -> W {
+  let s = n.line_rs_isqrt();
+  if s * s < n { s + W::from(1) } else { s }
+}
+
+// `wide` as a T if it is in range, otherwise the value of T nearest to it,
+// found by bisecting towards `inside`
+pub(crate) fn narrow_clamped<T: LineRSInt>(wide: T::Wide, inside: T) -> T {
+  if let Some(value) = T::line_rs_narrow(wide) {
+    return value;
+  }
+  let one = T::Wide::from(1);
+  let inside_wide = match inside.line_rs_widen() {
+    Some(inside_wide) => inside_wide,
+    None => return inside,
+  };
+  let (mut bad, mut good) = (wide, inside_wide);
+  while good - bad > one || bad - good > one {
+    let mid = bad + (good - bad) / T::Wide::from(2);
+    if T::line_rs_narrow(mid).is_some() {
+      good = mid;
+    } else {
+      bad = mid;
+    }
+  }
+  T::line_rs_narrow(good).unwrap_or(inside)
+}
+
+/// The unsigned integers used as `LineRSInt::Magnitude`.
+pub trait LineRSUint:
+  LineRSInt<Magnitude = Self> +
+  From<u8> +
+  core::ops::Div<Output = Self> +
+  core::ops::Rem<Output = Self>
+{
+  // saturates at usize::MAX
+  fn line_rs_to_usize(self) -> usize;
+  // None when n is too big for Self
+  fn line_rs_from_usize(n: usize) -> Option<Self>;
+
+  /// `(self * mul / div, self * mul % div)` without the product
+  /// overflowing. The quotient itself must fit, which holds whenever
+  /// `self <= div`.
+  fn line_rs_mul_div(self, mul: Self, div: Self) -> (Self, Self) {
+    // shift and add over the bits of `mul`, keeping every partial product
+    // as a quotient and remainder of `div` so no value exceeds the result.
+    let zero = Self::line_rs_zero();
+    let one = Self::line_rs_one();
+    let two = Self::line_rs_two();
+    let mut term = (self / div, self % div);
+    let mut product = (zero, zero);
+    let mut bits = mul;
+    loop {
+      if bits % two == one {
+        product = add_quotients(product, term, div, one);
+      }
+      bits = bits / two;
+      if bits == zero {
+        return product;
+      }
+      term = add_quotients(term, term, div, one);
+    }
+  }
+}
+
+// (q1 * div + r1) + (q2 * div + r2) as a quotient and remainder of div,
+// for remainders in [0, div)
+pub(crate) fn add_quotients<N>(a: (N, N), b: (N, N), div: N, one: N) -> (N, N)
+where
+  N: Copy + PartialOrd + core::ops::Add<Output = N> + core::ops::Sub<Output = N>,
+{
+  let (q1, r1) = a;
+  let (q2, r2) = b;
+  if r1 >= div - r2 {
+    (q1 + q2 + one, r1 - (div - r2))
+  } else {
+    (q1 + q2, r1 + r2)
+  }
 }
 
 macro_rules! line_rs_int_known_numbers {
-  ($t:ty, $zero:expr, $one:expr, $two:expr) => {
+  ($t:ty, $magnitude:ty, $wide:ty, $zero:expr, $one:expr, $two:expr) => {
     impl LineRSInt for $t {
+      type Magnitude = $magnitude;
+      type Wide = $wide;
+
       fn line_rs_zero() -> Self {
         $zero
       }
@@ -28,69 +266,106 @@ macro_rules! line_rs_int_known_numbers {
       fn line_rs_two() -> Self {
         $two
       }
+      fn line_rs_abs_diff(self, other: Self) -> $magnitude {
+        self.abs_diff(other)
+      }
+      // two's complement: wrapping by the magnitude reinterpreted as $t
+      // lands on the right value whenever that value is representable.
+      fn line_rs_add_magnitude(self, magnitude: $magnitude) -> Self {
+        self.wrapping_add(magnitude as $t)
+      }
+      fn line_rs_sub_magnitude(self, magnitude: $magnitude) -> Self {
+        self.wrapping_sub(magnitude as $t)
+      }
+      fn line_rs_widen(self) -> Option<$wide> {
+        <$wide as core::convert::TryFrom<$t>>::try_from(self).ok()
+      }
+      fn line_rs_narrow(wide: $wide) -> Option<Self> {
+        <$t as core::convert::TryFrom<$wide>>::try_from(wide).ok()
+      }
     }
   };
 }
 
-line_rs_int_known_numbers!(i8, 0, 1, 2);
-line_rs_int_known_numbers!(i16, 0, 1, 2);
-line_rs_int_known_numbers!(i32, 0, 1, 2);
-line_rs_int_known_numbers!(isize, 0, 1, 2);
-line_rs_int_known_numbers!(u8, 0, 1, 2);
-line_rs_int_known_numbers!(u16, 0, 1, 2);
-line_rs_int_known_numbers!(u32, 0, 1, 2);
-line_rs_int_known_numbers!(usize, 0, 1, 2);
+macro_rules! line_rs_uint_known_numbers {
+  ($t:ty) => {
+    impl LineRSUint for $t {
+      fn line_rs_to_usize(self) -> usize {
+        <usize as core::convert::TryFrom<$t>>::try_from(self).unwrap_or(usize::MAX)
+      }
+      fn line_rs_from_usize(n: usize) -> Option<Self> {
+        <$t as core::convert::TryFrom<usize>>::try_from(n).ok()
+      }
+    }
+  };
+  ($t:ty, $wide:ty) => {
+    impl LineRSUint for $t {
+      fn line_rs_to_usize(self) -> usize {
+        <usize as core::convert::TryFrom<$t>>::try_from(self).unwrap_or(usize::MAX)
+      }
+      fn line_rs_from_usize(n: usize) -> Option<Self> {
+        <$t as core::convert::TryFrom<usize>>::try_from(n).ok()
+      }
+      fn line_rs_mul_div(self, mul: Self, div: Self) -> (Self, Self) {
+        let product = self as $wide * mul as $wide;
+        ((product / div as $wide) as $t, (product % div as $wide) as $t)
+      }
+    }
+  };
+}
+
+line_rs_int_known_numbers!(i8, u8, i128, 0, 1, 2);
+line_rs_int_known_numbers!(i16, u16, i128, 0, 1, 2);
+line_rs_int_known_numbers!(i32, u32, i128, 0, 1, 2);
+line_rs_int_known_numbers!(i64, u64, i128, 0, 1, 2);
+line_rs_int_known_numbers!(i128, u128, i128, 0, 1, 2);
+line_rs_int_known_numbers!(isize, usize, i128, 0, 1, 2);
+line_rs_int_known_numbers!(u8, u8, i128, 0, 1, 2);
+line_rs_int_known_numbers!(u16, u16, i128, 0, 1, 2);
+line_rs_int_known_numbers!(u32, u32, i128, 0, 1, 2);
+line_rs_int_known_numbers!(u64, u64, i128, 0, 1, 2);
+line_rs_int_known_numbers!(u128, u128, i128, 0, 1, 2);
+line_rs_int_known_numbers!(usize, usize, i128, 0, 1, 2);
+
+line_rs_uint_known_numbers!(u8, u16);
+line_rs_uint_known_numbers!(u16, u32);
+line_rs_uint_known_numbers!(u32, u64);
+line_rs_uint_known_numbers!(u64, u128);
+line_rs_uint_known_numbers!(u128);
+line_rs_uint_known_numbers!(usize, u128);
 
-#[derive(Debug)]
-enum Sign {
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum Sign {
   Pos,
   Neg
 }
 
-#[derive(Debug)]
-pub struct SignedInt<
-  T: LineRSInt +
-    std::cmp::PartialOrd +
-    std::ops::Add<Output = T> +
-    std::ops::Sub<Output = T> +
-    std::ops::Mul<Output = T>
-> {
-  magnitude: T,
-  sign: Sign,
-}
-
-impl<
-  T: LineRSInt +
-    std::cmp::PartialOrd +
-    std::ops::Add<Output = T> +
-    std::ops::Sub<Output = T> +
-    std::ops::Mul<Output = T>
-> SignedInt<T> {
-  fn diff_of(a: T, b: T) -> SignedInt<T> {
-    if a >= b {
-      SignedInt {
-        magnitude: a - b,
-        sign: Sign::Pos,
-      }
-    } else {
-      SignedInt {
-        magnitude: b - a,
-        sign: Sign::Neg,
-      }
+impl Sign {
+  pub(crate) fn reverse(self) -> Sign {
+    match self {
+      Sign::Pos => Sign::Neg,
+      Sign::Neg => Sign::Pos,
     }
   }
+}
 
-  fn from(val: T) -> SignedInt<T> {
-    SignedInt::diff_of(val, T::line_rs_zero())
-  }
+// a signed value stored as an unsigned magnitude, so that the difference of
+// any two values of a type always fits.
+#[derive(Debug, Copy, Clone)]
+pub struct SignedInt<U: LineRSUint> {
+  pub(crate) magnitude: U,
+  pub(crate) sign: Sign,
+}
 
-  fn sub(self, rhs: T) -> SignedInt<T> {
-    let rhs_signed = SignedInt::from(rhs);
-    if let Sign::Neg = rhs_signed.sign {
-      // rhs negative, subtract becomes add
-      return self.add(rhs_signed.magnitude);
+impl<U: LineRSUint> SignedInt<U> {
+  pub(crate) fn diff_of<T: LineRSInt<Magnitude = U>>(a: T, b: T) -> SignedInt<U> {
+    SignedInt {
+      magnitude: a.line_rs_abs_diff(b),
+      sign: if a >= b { Sign::Pos } else { Sign::Neg },
     }
+  }
 
+  pub(crate) fn sub(self, rhs: U) -> SignedInt<U> {
     // -2 - 5 === -(2 + 5)
     if let Sign::Neg = self.sign {
       return SignedInt {
@@ -99,121 +374,573 @@ impl<
       }
     }
 
-    return SignedInt::diff_of(self.magnitude, rhs)
+    SignedInt::diff_of(self.magnitude, rhs)
   }
 
-  fn add(self, rhs: T) -> SignedInt<T> {
-    let rhs_signed = SignedInt::from(rhs);
-    if let Sign::Neg = rhs_signed.sign {
-      // rhs negative, add becomes subtract
-      return self.sub(rhs_signed.magnitude);
-    }
-
+  pub(crate) fn add(self, rhs: U) -> SignedInt<U> {
     // -5 + 2 === 2 - 5
     if let Sign::Neg = self.sign {
-      return rhs_signed.sub(self.magnitude)
+      return SignedInt::diff_of(rhs, self.magnitude)
     }
 
-    return SignedInt {
+    SignedInt {
       magnitude: self.magnitude + rhs,
       sign: Sign::Pos
     }
   }
 }
 
-pub fn calculate_line<
-  T: LineRSInt +
-    std::cmp::PartialOrd +
-    std::ops::Add<Output = T> +
-    std::ops::Sub<Output = T> +
-    std::ops::Mul<Output = T>
->(
-  p1: Point<T>,
-  p2: Point<T>,
-) -> Vec<Point<T>> {
-  /*
-           |
-        4  |  1
-     ------|------
-        3  |  2
-           |
-  */
-  // get the x and y segments of the line.
-  let mut x_diff = SignedInt::diff_of(p2.x, p1.x);
-  let mut y_diff = SignedInt::diff_of(p2.y, p1.y);
-
-  // bresenham assumption #1: x2 > x1 && y2 > y1
-  // i.e. use the magnitude values of the x/y vectors
-  // to calculate the line (disregard direction for now)
-
-  // bresenham assumption #2: 0 <= m <= 1
-  // swap the x/y vectors if the slope is greater than 45deg.
-  let swap_axes = x_diff.magnitude < y_diff.magnitude;
-  let mut x = p1.x;
-  let mut y = p1.y;
-  if swap_axes {
-    let tmp_diff = x_diff;
-    x_diff = y_diff;
-    y_diff = tmp_diff;
-    let tmp_x = x;
-    x = y;
-    y = tmp_x;
-  }
-
-  // derived formula in the bresenham line algorithm
-  let bresenham_2y = y_diff.magnitude * T::line_rs_two();
-  let bresenham_x = x_diff.magnitude;
-  let mut bresenham_diff = SignedInt::diff_of(bresenham_2y, bresenham_x);
-
-  let mut line = vec![p1];
-
-  let high = x_diff.magnitude;
-  let mut i = T::line_rs_zero();
-  loop {
-    if i >= high {
-      break;
-    }
-    i = i + T::line_rs_one();
+// moves `from` by `magnitude` in the direction of `sign`
+pub(crate) fn offset<T: LineRSInt>(from: T, sign: Sign, magnitude: T::Magnitude) -> T {
+  match sign {
+    Sign::Pos => from.line_rs_add_magnitude(magnitude),
+    Sign::Neg => from.line_rs_sub_magnitude(magnitude),
+  }
+}
+
+// the steps i in [0, len] for which `start` moved i times in the direction of
+// `sign` lands within [lo, hi], if there are any.
+fn index_range<T: LineRSInt>(
+  start: T,
+  sign: Sign,
+  lo: T,
+  hi: T,
+  len: T::Magnitude,
+) -> Option<(T::Magnitude, T::Magnitude)> {
+  let (near, far) = match sign {
+    Sign::Pos => (lo, hi),
+    Sign::Neg => (hi, lo),
+  };
+  let ahead = |v: T| match sign {
+    Sign::Pos => SignedInt::diff_of(v, start),
+    Sign::Neg => SignedInt::diff_of(start, v),
+  };
+  let (first, last) = (ahead(near), ahead(far));
+  if let Sign::Neg = last.sign {
+    return None;
+  }
+  let first = match first.sign {
+    Sign::Neg => T::Magnitude::line_rs_zero(),
+    Sign::Pos => first.magnitude,
+  };
+  let last = if last.magnitude < len { last.magnitude } else { len };
+  if first > last {
+    None
+  } else {
+    Some((first, last))
+  }
+}
+
+// one end of a line being walked: where it started, the current (possibly
+// axis-swapped) position, which way each axis moves, and the running error
+// term.
+#[derive(Debug, Clone)]
+struct Cursor<T: LineRSInt> {
+  origin_x: T,
+  origin_y: T,
+  x: T,
+  y: T,
+  x_sign: Sign,
+  y_sign: Sign,
+  // the running error term starts at y - tie_offset (see with_tie_break).
+  // after i steps the minor axis has moved floor((i * y + x - tie_offset) / x)
+  // times, which is what lets a cursor jump straight to any step.
+  tie_offset: T::Magnitude,
+  bresenham_diff: SignedInt<T::Magnitude>,
+}
+
+impl<T: LineRSInt> Cursor<T> {
+  fn new(
+    x: T,
+    y: T,
+    x_sign: Sign,
+    y_sign: Sign,
+    tie_offset: T::Magnitude,
+    y_magnitude: T::Magnitude,
+  ) -> Cursor<T> {
+    Cursor {
+      origin_x: x,
+      origin_y: y,
+      x,
+      y,
+      x_sign,
+      y_sign,
+      tie_offset,
+      bresenham_diff: SignedInt::diff_of(y_magnitude, tie_offset),
+    }
+  }
+
+  fn step(&mut self, x_magnitude: T::Magnitude, y_magnitude: T::Magnitude) {
     // println!("increment x");
-    x = match x_diff.sign {
-      Sign::Pos => {
-        if x_diff.magnitude == T::line_rs_zero() {
-          x
-        } else {
-          x + T::line_rs_one()
-        }
-      },
-      Sign::Neg => x - T::line_rs_one()
+    self.x = match self.x_sign {
+      Sign::Pos => self.x + T::line_rs_one(),
+      Sign::Neg => self.x - T::line_rs_one()
     };
     // println!("{:#?}", bresenham_d);
-    if let Sign::Neg = bresenham_diff.sign {
-      bresenham_diff = bresenham_diff.add(y_diff.magnitude * T::line_rs_two());
+    if let Sign::Neg = self.bresenham_diff.sign {
+      self.bresenham_diff = self.bresenham_diff.add(y_magnitude);
     } else {
       // println!("increment y");
-      y = match y_diff.sign {
-        Sign::Pos => {
-          if y_diff.magnitude == T::line_rs_zero() {
-            y
-          } else {
-            y + T::line_rs_one()
-          }
-        },
-        Sign::Neg => y - T::line_rs_one()
+      self.y = match self.y_sign {
+        Sign::Pos => self.y + T::line_rs_one(),
+        Sign::Neg => self.y - T::line_rs_one()
       };
-      bresenham_diff = bresenham_diff.add(y_diff.magnitude * T::line_rs_two()).sub(x_diff.magnitude * T::line_rs_two());
+      // 0 <= h < y <= x here, so subtracting first can't overflow
+      self.bresenham_diff = self.bresenham_diff
+        .sub(x_magnitude)
+        .add(y_magnitude);
+    }
+  }
+
+  // puts the cursor where `index` calls to `step` from its origin would
+  fn seek(&mut self, index: T::Magnitude, x_magnitude: T::Magnitude, y_magnitude: T::Magnitude) {
+    let tie_offset = self.tie_offset;
+    if index == T::Magnitude::line_rs_zero() {
+      self.x = self.origin_x;
+      self.y = self.origin_y;
+      self.bresenham_diff = SignedInt::diff_of(y_magnitude, tie_offset);
+      return;
+    }
+    // index * y = q * x + r, and the minor axis moved q times, plus once
+    // more if r has reached the tie offset. the error term is
+    // y - tie_offset + index * y - moves * x.
+    let (q, r) = index.line_rs_mul_div(y_magnitude, x_magnitude);
+    let below = SignedInt::diff_of(r, tie_offset);
+    self.x = offset(self.origin_x, self.x_sign, index);
+    if let Sign::Neg = below.sign {
+      self.y = offset(self.origin_y, self.y_sign, q);
+      self.bresenham_diff = below.add(y_magnitude);
+    } else {
+      self.y = offset(self.origin_y, self.y_sign, q + T::Magnitude::line_rs_one());
+      self.bresenham_diff = below.sub(x_magnitude).add(y_magnitude);
     }
+  }
+
+  // the first step at which the minor axis has moved `moves` times. only
+  // called with 1 <= moves <= y.
+  fn first_index_reaching(
+    &self,
+    moves: T::Magnitude,
+    x_magnitude: T::Magnitude,
+    y_magnitude: T::Magnitude,
+  ) -> T::Magnitude {
+    let zero = T::Magnitude::line_rs_zero();
+    let one = T::Magnitude::line_rs_one();
+    let tie_offset = self.tie_offset;
+    // ceil(((moves - 1) * x + tie_offset) / y), split into whole multiples
+    // of y so nothing overflows.
+    let (q, r) = (moves - one).line_rs_mul_div(x_magnitude, y_magnitude);
+    let (tie_q, tie_r) = (tie_offset / y_magnitude, tie_offset % y_magnitude);
+    let carry = if r == zero && tie_r == zero {
+      zero
+    } else if r <= y_magnitude - tie_r {
+      one
+    } else {
+      one + one
+    };
+    q + tie_q + carry
+  }
+}
+
+/// An axis-aligned rectangle covering the points from `min` to `max`, both
+/// inclusive. A rectangle whose `min` exceeds its `max` on either axis is
+/// empty.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rect<T> {
+  pub min: Point<T>,
+  pub max: Point<T>,
+}
+
+impl<T> Rect<T> {
+  pub fn new(min: Point<T>, max: Point<T>) -> Rect<T> {
+    Rect { min, max }
+  }
+}
+
+impl<T: PartialOrd> Rect<T> {
+  pub fn contains(&self, point: &Point<T>) -> bool {
+    self.min.x <= point.x && point.x <= self.max.x &&
+      self.min.y <= point.y && point.y <= self.max.y
+  }
+}
+
+/// Lazily yields the points of the line from `p1` to `p2`, both inclusive,
+/// in the same order as `calculate_line`.
+///
+/// The iterator is double-ended: `next_back` walks the same points from the
+/// `p2` end, so a line can be consumed from both ends at once.
+///
+/// Every pair of representable endpoints works, including ones spanning the
+/// whole range of the coordinate type. A line with more than `usize::MAX`
+/// points, which takes coordinates at least as wide as `usize`, has a
+/// `size_hint` of `(usize::MAX, None)`, and `len` panics on it.
+#[derive(Debug, Clone)]
+pub struct BresenhamIter<T: LineRSInt> {
+  x_magnitude: T::Magnitude,
+  y_magnitude: T::Magnitude,
+  swap_axes: bool,
+  front: Cursor<T>,
+  back: Cursor<T>,
+  // steps taken by each cursor; the front and back meet once these add up
+  // to x_magnitude
+  front_index: T::Magnitude,
+  back_index: T::Magnitude,
+  done: bool,
+}
+
+impl<T: LineRSInt> BresenhamIter<T> {
+  /// The classic line: when the ideal line passes exactly halfway between
+  /// two pixels, the one further along the direction of travel is chosen,
+  /// so `new(a, b)` and `new(b, a)` may differ in those pixels.
+  pub fn new(p1: Point<T>, p2: Point<T>) -> BresenhamIter<T> {
+    BresenhamIter::with_tie_break(p1, p2, false)
+  }
+
+  /// A line whose halfway ties are broken independently of direction, so
+  /// `symmetric(a, b)` yields exactly the points of `symmetric(b, a)` in
+  /// reverse order.
+  pub fn symmetric(p1: Point<T>, p2: Point<T>) -> BresenhamIter<T> {
+    BresenhamIter::with_tie_break(p1, p2, true)
+  }
+
+  fn with_tie_break(p1: Point<T>, p2: Point<T>, symmetric: bool) -> BresenhamIter<T> {
+    /*
+             |
+          4  |  1
+       ------|------
+          3  |  2
+             |
+    */
+    // get the x and y segments of the line.
+    let mut x_diff = SignedInt::diff_of(p2.x, p1.x);
+    let mut y_diff = SignedInt::diff_of(p2.y, p1.y);
+
+    // bresenham assumption #1: x2 > x1 && y2 > y1
+    // i.e. use the magnitude values of the x/y vectors
+    // to calculate the line (disregard direction for now)
+
+    // bresenham assumption #2: 0 <= m <= 1
+    // swap the x/y vectors if the slope is greater than 45deg.
+    let swap_axes = x_diff.magnitude < y_diff.magnitude;
+    let (mut x1, mut y1, mut x2, mut y2) = (p1.x, p1.y, p2.x, p2.y);
     if swap_axes {
-      line.push(Point { x: y, y: x });
+      core::mem::swap(&mut x_diff, &mut y_diff);
+      core::mem::swap(&mut x1, &mut y1);
+      core::mem::swap(&mut x2, &mut y2);
+    }
+
+    // ties (an error term of exactly zero) step the minor axis when walking
+    // from the front in the classic line. walking the other way with the
+    // opposite tie rule retraces the same pixels, so the back cursor always
+    // uses the opposite rule. the symmetric line makes the front rule depend
+    // on direction instead, so both orientations round ties the same way.
+    let front_ties_step = match x_diff.sign {
+      Sign::Neg => !symmetric,
+      Sign::Pos => true,
+    };
+
+    // derived formula in the bresenham line algorithm: the decision term
+    // starts at d = 2y - x and moves by 2y or 2y - 2x per step, which can
+    // need one bit more than the magnitudes themselves. every step changes
+    // d by an even amount, so d = 2h + (x % 2) for all steps, and only the
+    // half h = y - ceil(x / 2) is tracked. d >= 0 exactly when h >= 0, each
+    // step moves h by y or y - x, and h stays within [-x, y), so it always
+    // fits the magnitude type.
+    // a strict tie rule is the same as starting one lower: d > 0 <=> d - 1 >= 0,
+    // which makes the half y - (x / 2 + 1).
+    let x_magnitude = x_diff.magnitude;
+    let y_magnitude = y_diff.magnitude;
+    let two = T::Magnitude::line_rs_two();
+    let tie_offset = |ties_step: bool| {
+      if ties_step {
+        x_magnitude - x_magnitude / two
+      } else {
+        x_magnitude / two + T::Magnitude::line_rs_one()
+      }
+    };
+
+    BresenhamIter {
+      x_magnitude,
+      y_magnitude,
+      swap_axes,
+      front: Cursor::new(
+        x1,
+        y1,
+        x_diff.sign,
+        y_diff.sign,
+        tie_offset(front_ties_step),
+        y_magnitude
+      ),
+      back: Cursor::new(
+        x2,
+        y2,
+        x_diff.sign.reverse(),
+        y_diff.sign.reverse(),
+        tie_offset(!front_ties_step),
+        y_magnitude
+      ),
+      front_index: T::Magnitude::line_rs_zero(),
+      back_index: T::Magnitude::line_rs_zero(),
+      done: false,
+    }
+  }
+
+  /// Restricts the line to the points inside `rect`, keeping exactly the
+  /// pixels the unclipped line has there.
+  ///
+  /// The entry and exit points are computed directly (like Liang–Barsky,
+  /// but on the integer steps of the line rather than on the ideal
+  /// segment), so the cost does not depend on how much of the line lies
+  /// outside the rectangle. Any part of the line that lies inside `rect`
+  /// is contiguous, so the result is again a single run of points.
+  pub fn clip(mut self, rect: Rect<T>) -> BresenhamIter<T> {
+    if self.done {
+      return self;
+    }
+    let (lo_x, hi_x, lo_y, hi_y) = if self.swap_axes {
+      (rect.min.y, rect.max.y, rect.min.x, rect.max.x)
+    } else {
+      (rect.min.x, rect.max.x, rect.min.y, rect.max.y)
+    };
+    let x_range = index_range(self.front.origin_x, self.front.x_sign, lo_x, hi_x, self.x_magnitude);
+    let y_range = index_range(self.front.origin_y, self.front.y_sign, lo_y, hi_y, self.y_magnitude);
+    let (x_first, x_last, y_first, y_last) = match (x_range, y_range) {
+      (Some((x_first, x_last)), Some((y_first, y_last))) => (x_first, x_last, y_first, y_last),
+      _ => {
+        self.done = true;
+        return self;
+      }
+    };
+
+    // the minor axis never moves backwards, so the steps at which it has
+    // moved between y_first and y_last times form a range too.
+    let zero = T::Magnitude::line_rs_zero();
+    let one = T::Magnitude::line_rs_one();
+    let mut first = self.front_index;
+    let mut last = self.x_magnitude - self.back_index;
+    if x_first > first {
+      first = x_first;
+    }
+    if x_last < last {
+      last = x_last;
+    }
+    if y_first > zero {
+      let reaching = self.front.first_index_reaching(y_first, self.x_magnitude, self.y_magnitude);
+      if reaching > first {
+        first = reaching;
+      }
+    }
+    if y_last < self.y_magnitude {
+      let past = self.front.first_index_reaching(y_last + one, self.x_magnitude, self.y_magnitude) - one;
+      if past < last {
+        last = past;
+      }
+    }
+    if first > last {
+      self.done = true;
+      return self;
+    }
+
+    self.front_index = first;
+    self.back_index = self.x_magnitude - last;
+    self.front.seek(self.front_index, self.x_magnitude, self.y_magnitude);
+    self.back.seek(self.back_index, self.x_magnitude, self.y_magnitude);
+    self
+  }
+
+  fn point_of(&self, cursor: &Cursor<T>) -> Point<T> {
+    if self.swap_axes {
+      Point { x: cursor.y, y: cursor.x }
     } else {
-      line.push(Point { x, y });
+      Point { x: cursor.x, y: cursor.y }
     }
   }
-  return line;
+
+  // the step of the whole line, ignoring any clipping, that lands on `point`
+  fn index_of(&self, point: Point<T>) -> Option<T::Magnitude> {
+    let (x, y) = if self.swap_axes { (point.y, point.x) } else { (point.x, point.y) };
+    // the major axis moves on every step, so it alone fixes the step, and
+    // the point is on the line if the minor axis is there at that step
+    let (index, _) = index_range(self.front.origin_x, self.front.x_sign, x, x, self.x_magnitude)?;
+    let mut cursor = self.front.clone();
+    cursor.seek(index, self.x_magnitude, self.y_magnitude);
+    if cursor.y == y { Some(index) } else { None }
+  }
+
+  // steps left between the front and back cursors
+  fn remaining(&self) -> T::Magnitude {
+    self.x_magnitude - self.front_index - self.back_index
+  }
 }
 
-#[cfg(test)]
+impl<T: LineRSInt> Iterator for BresenhamIter<T> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    if self.done {
+      return None;
+    }
+    let point = self.point_of(&self.front);
+    if self.remaining() == T::Magnitude::line_rs_zero() {
+      self.done = true;
+    } else {
+      self.front_index = self.front_index + T::Magnitude::line_rs_one();
+      self.front.step(self.x_magnitude, self.y_magnitude);
+    }
+    Some(point)
+  }
+
+  // jumps straight to the point, see line_point_at
+  fn nth(&mut self, n: usize) -> Option<Point<T>> {
+    if self.done {
+      return None;
+    }
+    match T::Magnitude::line_rs_from_usize(n) {
+      Some(steps) if steps <= self.remaining() => {
+        self.front_index = self.front_index + steps;
+        self.front.seek(self.front_index, self.x_magnitude, self.y_magnitude);
+        self.next()
+      },
+      _ => {
+        self.done = true;
+        None
+      },
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    if self.done {
+      return (0, Some(0));
+    }
+    match self.remaining().line_rs_to_usize().checked_add(1) {
+      Some(len) => (len, Some(len)),
+      None => (usize::MAX, None),
+    }
+  }
+}
+
+impl<T: LineRSInt> DoubleEndedIterator for BresenhamIter<T> {
+  fn next_back(&mut self) -> Option<Point<T>> {
+    if self.done {
+      return None;
+    }
+    let point = self.point_of(&self.back);
+    if self.remaining() == T::Magnitude::line_rs_zero() {
+      self.done = true;
+    } else {
+      self.back_index = self.back_index + T::Magnitude::line_rs_one();
+      self.back.step(self.x_magnitude, self.y_magnitude);
+    }
+    Some(point)
+  }
+
+  fn nth_back(&mut self, n: usize) -> Option<Point<T>> {
+    if self.done {
+      return None;
+    }
+    match T::Magnitude::line_rs_from_usize(n) {
+      Some(steps) if steps <= self.remaining() => {
+        self.back_index = self.back_index + steps;
+        self.back.seek(self.back_index, self.x_magnitude, self.y_magnitude);
+        self.next_back()
+      },
+      _ => {
+        self.done = true;
+        None
+      },
+    }
+  }
+}
+
+impl<T: LineRSInt> ExactSizeIterator for BresenhamIter<T> {}
+
+impl<T: LineRSInt> FusedIterator for BresenhamIter<T> {}
+
+/// Calls `f` with each point of the line from `p1` to `p2`, in the order of
+/// `calculate_line`, without allocating. Traversal stops as soon as `f`
+/// returns `ControlFlow::Break`, and the break value is handed back together
+/// with the point it was returned for.
+pub fn for_each_line_point<T: LineRSInt, B, F: FnMut(Point<T>) -> ControlFlow<B>>(
+  p1: Point<T>,
+  p2: Point<T>,
+  mut f: F,
+) -> ControlFlow<(B, Point<T>)> {
+  for point in BresenhamIter::new(p1, p2) {
+    if let ControlFlow::Break(value) = f(point) {
+      return ControlFlow::Break((value, point));
+    }
+  }
+  ControlFlow::Continue(())
+}
+
+/// The point at `index` along the line from `p1` to `p2`, which is
+/// `calculate_line(p1, p2)[index]`, or `None` past the end of the line.
+///
+/// The point is found directly from the closed form of the line's error
+/// term with one multiplication and division, however far along it is.
+/// `BresenhamIter::nth` and `nth_back` skip ahead the same way.
+pub fn line_point_at<T: LineRSInt>(p1: Point<T>, p2: Point<T>, index: usize) -> Option<Point<T>> {
+  BresenhamIter::new(p1, p2).nth(index)
+}
+
+/// The index of `point` along the line from `p1` to `p2`, so that
+/// `line_point_at(p1, p2, index) == Some(point)`, or `None` when the line
+/// doesn't pass through `point`.
+///
+/// Like `line_point_at` this takes constant time: the point's coordinate
+/// along the longer axis gives the only index it could have. A point whose
+/// index is past `usize::MAX`, which takes coordinates wider than `usize`,
+/// is also `None`, as `line_point_at` can't reach it either.
+pub fn line_point_index<T: LineRSInt>(p1: Point<T>, p2: Point<T>, point: Point<T>) -> Option<usize> {
+  let index = BresenhamIter::new(p1, p2).index_of(point)?;
+  // line_rs_to_usize saturates, so check it comes back the same
+  let narrowed = index.line_rs_to_usize();
+  if T::Magnitude::line_rs_from_usize(narrowed) == Some(index) { Some(narrowed) } else { None }
+}
+
+#[cfg(feature = "alloc")]
+pub fn calculate_line<T: LineRSInt>(
+  p1: Point<T>,
+  p2: Point<T>,
+) -> Vec<Point<T>> {
+  BresenhamIter::new(p1, p2).collect()
+}
+
+/// The points of `calculate_line(p1, p2)` that lie inside `rect`, found
+/// without walking the parts of the line outside it. See
+/// `BresenhamIter::clip`.
+#[cfg(feature = "alloc")]
+pub fn calculate_clipped_line<T: LineRSInt>(
+  p1: Point<T>,
+  p2: Point<T>,
+  rect: Rect<T>,
+) -> Vec<Point<T>> {
+  BresenhamIter::new(p1, p2).clip(rect).collect()
+}
+
+/// Like `calculate_line`, but `calculate_symmetric_line(b, a)` is always the
+/// reverse of `calculate_symmetric_line(a, b)`. See `BresenhamIter::symmetric`.
+#[cfg(feature = "alloc")]
+pub fn calculate_symmetric_line<T: LineRSInt>(
+  p1: Point<T>,
+  p2: Point<T>,
+) -> Vec<Point<T>> {
+  BresenhamIter::symmetric(p1, p2).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
+  use super::LineRSInt;
+  use super::LineRSUint;
+  use super::Rect;
+  use super::calculate_clipped_line;
   use super::calculate_line;
+  use super::calculate_symmetric_line;
+  use super::for_each_line_point;
+  use super::line_point_at;
+  use super::line_point_index;
+  use core::ops::ControlFlow;
+  use super::BresenhamIter;
   use super::Point;
 
   #[test]
@@ -332,4 +1059,488 @@ mod tests {
     ];
     assert_eq!(line, expected);
   }
+
+  #[test]
+  fn iter_matches_calculate_line() {
+    let p1 = Point::new(-4, 7);
+    let p2 = Point::new(9, -2);
+    let line: Vec<_> = BresenhamIter::new(p1, p2).collect();
+    assert_eq!(line, calculate_line(p1, p2));
+    assert_eq!(line.len(), 14);
+  }
+
+  #[test]
+  fn iter_is_lazy_and_exact_size() {
+    let mut iter = BresenhamIter::new(Point::new(0u8, 0), Point::new(200, 50));
+    assert_eq!(iter.len(), 201);
+    assert_eq!(iter.next(), Some(Point::new(0, 0)));
+    assert_eq!(iter.next(), Some(Point::new(1, 0)));
+    assert_eq!(iter.size_hint(), (199, Some(199)));
+    let last = iter.by_ref().last();
+    assert_eq!(last, Some(Point::new(200, 50)));
+    assert_eq!(iter.len(), 0);
+    assert_eq!(iter.next(), None);
+  }
+
+  #[test]
+  fn point_at_matches_calculate_line() {
+    for x1 in -6..=6 {
+      for y1 in -6..=6 {
+        for &(x2, y2) in &[(5, -4), (-6, -1), (2, 7), (0, 0), (-3, 6)] {
+          let (p1, p2) = (Point::new(x1, y1), Point::new(x2, y2));
+          let line = calculate_line(p1, p2);
+          for (i, &p) in line.iter().enumerate() {
+            assert_eq!(line_point_at(p1, p2, i), Some(p));
+          }
+          assert_eq!(line_point_at(p1, p2, line.len()), None);
+          assert_eq!(line_point_at(p1, p2, usize::MAX), None);
+
+          // nth from either end, mixed with plain steps
+          let symmetric = calculate_symmetric_line(p1, p2);
+          let mut iter = BresenhamIter::symmetric(p1, p2);
+          let mut expected = symmetric.iter().copied();
+          loop {
+            let (front, back) = (iter.nth(1), iter.nth_back(2));
+            assert_eq!(front, expected.nth(1));
+            assert_eq!(back, expected.nth_back(2));
+            assert_eq!(iter.len(), expected.len());
+            if front.is_none() {
+              break;
+            }
+          }
+        }
+      }
+    }
+
+    let (p1, p2) = (Point::new(u64::MAX, 0), Point::new(0, u64::MAX / 3));
+    assert_eq!(line_point_at(p1, p2, 0), Some(p1));
+    assert_eq!(line_point_at(p1, p2, u64::MAX as usize), Some(p2));
+    assert_eq!(line_point_at(p1, p2, 3 << 40), Some(Point::new(u64::MAX - (3 << 40), 1 << 40)));
+    let (p1, p2) = (Point::new(0u8, 0), Point::new(255, 255));
+    assert_eq!(line_point_at(p1, p2, 256), None);
+  }
+
+  #[test]
+  fn point_index_matches_calculate_line() {
+    for &(x1, y1) in &[(0, 0), (3, -2), (-5, 4)] {
+      for x2 in -7..=7 {
+        for y2 in -7..=7 {
+          let (p1, p2) = (Point::new(x1, y1), Point::new(x2, y2));
+          let line = calculate_line(p1, p2);
+          for x in -9..=9 {
+            for y in -9..=9 {
+              let point = Point::new(x, y);
+              assert_eq!(line_point_index(p1, p2, point), line.iter().position(|&p| p == point), "{:?}", point);
+            }
+          }
+        }
+      }
+    }
+
+    let (p1, p2) = (Point::new(i8::MIN, i8::MAX), Point::new(i8::MAX, i8::MIN + 100));
+    let line = calculate_line(p1, p2);
+    for (i, &p) in line.iter().enumerate() {
+      assert_eq!(line_point_index(p1, p2, p), Some(i));
+      assert_eq!(line_point_index(p1, p2, Point::new(p.x, p.y - 1)), None);
+    }
+    let (p1, p2) = (Point::new(u64::MAX, 0), Point::new(0, u64::MAX / 3));
+    assert_eq!(line_point_index(p1, p2, Point::new(u64::MAX - (3 << 40), 1 << 40)), Some(3 << 40));
+    assert_eq!(line_point_index(p1, p2, Point::new(u64::MAX - (3 << 40), (1 << 40) + 1)), None);
+
+    let (p1, p2) = (Point::new(0u128, 0), Point::new(u128::MAX, 0));
+    let last = Point::new(usize::MAX as u128, 0);
+    assert_eq!(line_point_index(p1, p2, last), Some(usize::MAX));
+    assert_eq!(line_point_at(p1, p2, usize::MAX), Some(last));
+    assert_eq!(line_point_index(p1, p2, Point::new(usize::MAX as u128 + 1, 0)), None);
+    assert_eq!(line_point_index(p1, p2, p2), None);
+  }
+
+  #[test]
+  fn next_back_retraces_forward_points() {
+    for x1 in -5..=5 {
+      for y1 in -5..=5 {
+        let p1 = Point::new(x1, y1);
+        let p2 = Point::new(3, -2);
+        let forward = calculate_line(p1, p2);
+        let mut backward: Vec<_> = BresenhamIter::new(p1, p2).rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        // meet in the middle, alternating ends
+        let mut iter = BresenhamIter::new(p1, p2);
+        let mut head = vec![];
+        let mut tail = vec![];
+        while let Some(p) = iter.next() {
+          head.push(p);
+          match iter.next_back() {
+            Some(p) => tail.push(p),
+            None => break,
+          }
+        }
+        tail.reverse();
+        head.extend(tail);
+        assert_eq!(forward, head);
+      }
+    }
+  }
+
+  #[test]
+  fn symmetric_line_reverses() {
+    // the classic line rounds the halfway point away from its start
+    assert_eq!(
+      calculate_line(Point::new(0, 0), Point::new(2, 1)),
+      vec![Point::new(0, 0), Point::new(1, 1), Point::new(2, 1)]
+    );
+    assert_eq!(
+      calculate_line(Point::new(2, 1), Point::new(0, 0)),
+      vec![Point::new(2, 1), Point::new(1, 0), Point::new(0, 0)]
+    );
+    for x1 in -5..=5 {
+      for y1 in -5..=5 {
+        let a = Point::new(x1, y1);
+        let b = Point::new(-1, 4);
+        let mut line = calculate_symmetric_line(a, b);
+        line.reverse();
+        assert_eq!(line, calculate_symmetric_line(b, a));
+      }
+    }
+  }
+
+  macro_rules! width_test {
+    ($name:ident, $t:ty) => {
+      #[test]
+      fn $name() {
+        let line = calculate_line(Point::<$t>::new(1, 2), Point::new(7, 5));
+        let expected: Vec<Point<$t>> = vec![
+          Point::new(1, 2),
+          Point::new(2, 3),
+          Point::new(3, 3),
+          Point::new(4, 4),
+          Point::new(5, 4),
+          Point::new(6, 5),
+          Point::new(7, 5),
+        ];
+        assert_eq!(line, expected);
+      }
+    };
+  }
+
+  width_test!(width_i8, i8);
+  width_test!(width_i16, i16);
+  width_test!(width_i32, i32);
+  width_test!(width_i64, i64);
+  width_test!(width_i128, i128);
+  width_test!(width_isize, isize);
+  width_test!(width_u8, u8);
+  width_test!(width_u16, u16);
+  width_test!(width_u32, u32);
+  width_test!(width_u64, u64);
+  width_test!(width_u128, u128);
+  width_test!(width_usize, usize);
+
+  #[test]
+  fn with_i64_world_coordinates() {
+    let far = 1i64 << 40;
+    let line = calculate_line(Point::new(far, -far), Point::new(far - 3, -far + 1));
+    let expected = vec![
+      Point::new(far, -far),
+      Point::new(far - 1, -far),
+      Point::new(far - 2, -far + 1),
+      Point::new(far - 3, -far + 1),
+    ];
+    assert_eq!(line, expected);
+  }
+
+  #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+  struct TileCoord(i32);
+
+  impl core::ops::Add for TileCoord {
+    type Output = TileCoord;
+    fn add(self, rhs: TileCoord) -> TileCoord {
+      TileCoord(self.0 + rhs.0)
+    }
+  }
+
+  impl core::ops::Sub for TileCoord {
+    type Output = TileCoord;
+    fn sub(self, rhs: TileCoord) -> TileCoord {
+      TileCoord(self.0 - rhs.0)
+    }
+  }
+
+  impl core::ops::Mul for TileCoord {
+    type Output = TileCoord;
+    fn mul(self, rhs: TileCoord) -> TileCoord {
+      TileCoord(self.0 * rhs.0)
+    }
+  }
+
+  impl LineRSInt for TileCoord {
+    type Magnitude = u32;
+    type Wide = i128;
+
+    fn line_rs_zero() -> Self {
+      TileCoord(0)
+    }
+    fn line_rs_one() -> Self {
+      TileCoord(1)
+    }
+    fn line_rs_two() -> Self {
+      TileCoord(2)
+    }
+    fn line_rs_abs_diff(self, other: Self) -> u32 {
+      self.0.line_rs_abs_diff(other.0)
+    }
+    fn line_rs_add_magnitude(self, magnitude: u32) -> Self {
+      TileCoord(self.0.line_rs_add_magnitude(magnitude))
+    }
+    fn line_rs_sub_magnitude(self, magnitude: u32) -> Self {
+      TileCoord(self.0.line_rs_sub_magnitude(magnitude))
+    }
+    fn line_rs_widen(self) -> Option<i128> {
+      self.0.line_rs_widen()
+    }
+    fn line_rs_narrow(wide: i128) -> Option<Self> {
+      i32::line_rs_narrow(wide).map(TileCoord)
+    }
+  }
+
+  #[test]
+  fn with_newtype() {
+    let line = calculate_line(
+      Point::new(TileCoord(3), TileCoord(9)),
+      Point::new(TileCoord(1), TileCoord(1))
+    );
+    let expected: Vec<_> = calculate_line(Point::new(3, 9), Point::new(1, 1))
+      .into_iter()
+      .map(|p| Point::new(TileCoord(p.x), TileCoord(p.y)))
+      .collect();
+    assert_eq!(line, expected);
+  }
+
+  #[test]
+  fn full_range_u8_and_i8() {
+    // compare against the same lines drawn in a type with room to spare
+    let to_i64 = |line: Vec<Point<u8>>| -> Vec<Point<i64>> {
+      line.into_iter().map(|p| Point::new(p.x as i64, p.y as i64)).collect()
+    };
+    let ends = [0u8, 1, 51, 128, 200, 254, 255];
+    for &x1 in &ends {
+      for &y1 in &ends {
+        for &x2 in &ends {
+          for &y2 in &ends {
+            let line = calculate_line(Point::new(x1, y1), Point::new(x2, y2));
+            let wide = calculate_line(
+              Point::new(x1 as i64, y1 as i64),
+              Point::new(x2 as i64, y2 as i64)
+            );
+            assert_eq!(to_i64(line), wide);
+
+            let (x1, y1, x2, y2) = (x1 as i8, y1 as i8, x2 as i8, y2 as i8);
+            let line: Vec<_> = calculate_symmetric_line(Point::new(x1, y1), Point::new(x2, y2))
+              .into_iter()
+              .map(|p| Point::new(p.x as i64, p.y as i64))
+              .collect();
+            let wide = calculate_symmetric_line(
+              Point::new(x1 as i64, y1 as i64),
+              Point::new(x2 as i64, y2 as i64)
+            );
+            assert_eq!(line, wide);
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn full_range_i128() {
+    let mut iter = BresenhamIter::new(
+      Point::new(i128::MIN, i128::MIN),
+      Point::new(i128::MAX, i128::MIN + 3)
+    );
+    assert_eq!(iter.size_hint(), (usize::MAX, None));
+    assert_eq!(iter.next(), Some(Point::new(i128::MIN, i128::MIN)));
+    assert_eq!(iter.next(), Some(Point::new(i128::MIN + 1, i128::MIN)));
+    assert_eq!(iter.next_back(), Some(Point::new(i128::MAX, i128::MIN + 3)));
+    assert_eq!(iter.next_back(), Some(Point::new(i128::MAX - 1, i128::MIN + 3)));
+
+    let mut iter = BresenhamIter::new(Point::new(u128::MAX, 0), Point::new(u128::MAX - 2, u128::MAX));
+    assert_eq!(iter.next(), Some(Point::new(u128::MAX, 0)));
+    assert_eq!(iter.next(), Some(Point::new(u128::MAX, 1)));
+    assert_eq!(iter.next_back(), Some(Point::new(u128::MAX - 2, u128::MAX)));
+    assert_eq!(iter.next_back(), Some(Point::new(u128::MAX - 2, u128::MAX - 1)));
+  }
+
+  #[test]
+  fn size_hint_past_usize_max() {
+    let iter = BresenhamIter::new(Point::new(0u128, 0), Point::new(u128::MAX, 0));
+    assert_eq!(iter.size_hint(), (usize::MAX, None));
+    let iter = BresenhamIter::new(Point::new(0u128, 0), Point::new(usize::MAX as u128 - 1, 0));
+    assert_eq!(iter.len(), usize::MAX);
+  }
+
+  #[test]
+  #[should_panic]
+  fn len_panics_past_usize_max() {
+    BresenhamIter::new(Point::new(0u128, 0), Point::new(usize::MAX as u128, 0)).len();
+  }
+
+  #[test]
+  fn for_each_visits_whole_line() {
+    let mut visited = vec![];
+    let result: ControlFlow<((), _)> = for_each_line_point(Point::new(3, 9), Point::new(1, 1), |p| {
+      visited.push(p);
+      ControlFlow::Continue(())
+    });
+    assert_eq!(result, ControlFlow::Continue(()));
+    assert_eq!(visited, calculate_line(Point::new(3, 9), Point::new(1, 1)));
+  }
+
+  #[test]
+  fn for_each_stops_at_break() {
+    // a wall along x == 4 stops the projectile
+    let mut visited = 0;
+    let result = for_each_line_point(Point::new(0u32, 0), Point::new(10, 3), |p| {
+      visited += 1;
+      if p.x == 4 {
+        ControlFlow::Break("wall")
+      } else {
+        ControlFlow::Continue(())
+      }
+    });
+    assert_eq!(result, ControlFlow::Break(("wall", Point::new(4, 1))));
+    assert_eq!(visited, 5);
+  }
+
+  #[test]
+  fn mul_div_without_overflow() {
+    assert_eq!(200u8.line_rs_mul_div(250, 255), (196, 20));
+    assert_eq!(u128::MAX.line_rs_mul_div(u128::MAX, u128::MAX), (u128::MAX, 0));
+    assert_eq!((u128::MAX - 1).line_rs_mul_div(u128::MAX - 2, u128::MAX), (u128::MAX - 3, 2));
+    assert_eq!(7u128.line_rs_mul_div(9, 10), (6, 3));
+  }
+
+  #[test]
+  fn clip_keeps_unclipped_pixels() {
+    let rects = [
+      Rect::new(Point::new(-2, -3), Point::new(3, 2)),
+      Rect::new(Point::new(0, 0), Point::new(0, 0)),
+      Rect::new(Point::new(-6, 1), Point::new(6, 1)),
+      Rect::new(Point::new(4, -6), Point::new(9, 6)),
+      Rect::new(Point::new(2, 2), Point::new(1, 1)),
+    ];
+    for x1 in -7..=7 {
+      for y1 in -7..=7 {
+        for &(x2, y2) in &[(5, -4), (-6, -1), (2, 7), (0, 0)] {
+          let p1 = Point::new(x1, y1);
+          let p2 = Point::new(x2, y2);
+          for rect in &rects {
+            let expected: Vec<_> = calculate_line(p1, p2)
+              .into_iter()
+              .filter(|p| rect.contains(p))
+              .collect();
+            assert_eq!(calculate_clipped_line(p1, p2, *rect), expected);
+
+            let symmetric: Vec<_> = calculate_symmetric_line(p1, p2)
+              .into_iter()
+              .filter(|p| rect.contains(p))
+              .collect();
+            let clipped = BresenhamIter::symmetric(p1, p2).clip(*rect);
+            assert_eq!(clipped.len(), symmetric.len());
+            let mut backward: Vec<_> = clipped.rev().collect();
+            backward.reverse();
+            assert_eq!(backward, symmetric);
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn clip_far_off_screen_line() {
+    let p1 = Point::new(-1_000_003i64, -333_335);
+    let p2 = Point::new(2_000_000, 666_667);
+    let screen = Rect::new(Point::new(0, 0), Point::new(639, 479));
+    let expected: Vec<_> = calculate_line(p1, p2)
+      .into_iter()
+      .filter(|p| screen.contains(p))
+      .collect();
+    let clipped = BresenhamIter::new(p1, p2).clip(screen);
+    assert_eq!(clipped.len(), 640);
+    assert_eq!(clipped.collect::<Vec<_>>(), expected);
+
+    let ends = [0u8, 3, 128, 252, 255];
+    let bounds = [0u8, 1, 100, 200, 254, 255];
+    for &x1 in &ends {
+      for &y2 in &ends {
+        let (p1, p2) = (Point::new(x1, 255), Point::new(255 - x1, y2));
+        for &lo in &bounds {
+          for &hi in &bounds {
+            let rect = Rect::new(Point::new(lo, lo / 2), Point::new(hi, hi));
+            let expected: Vec<_> = calculate_line(p1, p2)
+              .into_iter()
+              .filter(|p| rect.contains(p))
+              .collect();
+            assert_eq!(calculate_clipped_line(p1, p2, rect), expected);
+          }
+        }
+      }
+    }
+
+    let outside = Rect::new(Point::new(0, 400), Point::new(100, 479));
+    assert_eq!(BresenhamIter::new(p1, p2).clip(outside).next(), None);
+  }
+}
+
+// the iterator and visitor APIs are all an embedded target without an
+// allocator gets, so these run under every feature set
+#[cfg(test)]
+mod no_alloc_tests {
+  use super::for_each_line_point;
+  use core::ops::ControlFlow;
+  use super::BresenhamIter;
+  use super::Point;
+
+  const LINE: [Point<i16>; 9] = [
+    Point { x: 3, y: 9 },
+    Point { x: 3, y: 8 },
+    Point { x: 2, y: 7 },
+    Point { x: 2, y: 6 },
+    Point { x: 2, y: 5 },
+    Point { x: 2, y: 4 },
+    Point { x: 1, y: 3 },
+    Point { x: 1, y: 2 },
+    Point { x: 1, y: 1 },
+  ];
+
+  #[test]
+  fn iter_without_alloc() {
+    let mut iter = BresenhamIter::new(Point::new(3, 9), Point::new(1, 1));
+    assert_eq!(iter.len(), LINE.len());
+    for &expected in &LINE {
+      assert_eq!(iter.next(), Some(expected));
+    }
+    assert_eq!(iter.next(), None);
+    assert!(BresenhamIter::new(Point::new(3, 9), Point::new(1, 1)).rev().eq(LINE.iter().rev().copied()));
+  }
+
+  #[test]
+  fn for_each_without_alloc() {
+    // an lcd framebuffer of one bit per pixel
+    let mut framebuffer = [0u16; 10];
+    let result: ControlFlow<((), _)> = for_each_line_point(Point::new(3i16, 9), Point::new(1, 1), |p| {
+      framebuffer[p.y as usize] |= 1 << p.x;
+      ControlFlow::Continue(())
+    });
+    assert_eq!(result, ControlFlow::Continue(()));
+    assert_eq!(framebuffer, [0, 2, 2, 2, 4, 4, 4, 4, 8, 8]);
+
+    let mut visited = 0;
+    let result = for_each_line_point(Point::new(3i16, 9), Point::new(1, 1), |p| {
+      visited += 1;
+      if p.y == 4 { ControlFlow::Break(p.x) } else { ControlFlow::Continue(()) }
+    });
+    assert_eq!(result, ControlFlow::Break((2, LINE[5])));
+    assert_eq!(visited, 6);
+  }
 }
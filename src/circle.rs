@@ -0,0 +1,347 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{ceil_div, ceil_sqrt, narrow_clamped, LineRSInt, LineRSWide, Point, Span};
+
+/// Lazily yields the pixels of the circle of `radius` about `center`, each
+/// exactly once, using the midpoint algorithm.
+///
+/// The first octant is walked one step at a time, and each step yields its
+/// reflections into all eight octants, leaving out the ones that land on
+/// the same pixel where octants meet. The pixels are therefore not in order
+/// around the circle.
+///
+/// The arithmetic is done in `LineRSInt::Wide` and needs
+/// `4 * (radius + 1) * (radius + 1)` to fit there, which it always does for
+/// the primitive types. Pixels outside the range of `T` are skipped, and
+/// the walk only covers the part of the octant that can reach that range,
+/// so a radius far beyond it costs no more than one within it. A radius of
+/// 0 is just the centre.
+#[derive(Debug, Clone)]
+pub struct CircleIter<T: LineRSInt> {
+  cx: T::Wide,
+  cy: T::Wide,
+  // the current pixel of the first octant, relative to the centre, and the
+  // midpoint decision term for the next one
+  x: T::Wide,
+  y: T::Wide,
+  d: T::Wide,
+  // the furthest offset from the centre in range of T on either axis
+  limit: T::Wide,
+  // which of the eight reflections of (x, y) comes next
+  reflection: u8,
+  done: bool,
+}
+
+impl<T: LineRSInt> CircleIter<T> {
+  pub fn new(center: Point<T>, radius: u32) -> CircleIter<T> {
+    let zero = T::Wide::from(0);
+    let one = T::Wide::from(1);
+    let two = T::Wide::from(2);
+    let four = T::Wide::from(4);
+    let r = T::Wide::from(radius);
+    let widened = center.x.line_rs_widen().zip(center.y.line_rs_widen());
+    let (cx, cy) = widened.unwrap_or((zero, zero));
+    // a pixel in range has both its offsets within the limit, so the walk
+    // can start at the first column whose pixel is, the smallest x with
+    // (2 limit + 1)^2 >= 4(r^2 - x^2), and stop once x passes the limit
+    let reach = |from: T, wide: T::Wide| clamp_offset(from, wide, r).max(-clamp_offset(from, wide, -r));
+    let limit = reach(center.x, cx).max(reach(center.y, cy));
+    let outside = four * r * r - (two * limit + one) * (two * limit + one);
+    let x = if outside > zero { ceil_sqrt(ceil_div(outside, four)) } else { zero };
+    let y = ceil_sqrt(four * (r * r - x * x)) / two;
+    CircleIter {
+      cx,
+      cy,
+      x,
+      y,
+      // (x + 1)^2 + (y - 1/2)^2 - r^2 less a quarter, which is 1 - r at the
+      // top: every change to it is an integer, so the sign test comes out
+      // the same
+      d: (x + one) * (x + one) + y * y - y - r * r,
+      limit,
+      reflection: 0,
+      done: widened.is_none() || x > y,
+    }
+  }
+}
+
+impl<T: LineRSInt> Iterator for CircleIter<T> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    let zero = T::Wide::from(0);
+    let one = T::Wide::from(1);
+    let two = T::Wide::from(2);
+    while !self.done {
+      if self.reflection == 8 {
+        if self.d < zero {
+          self.d = self.d + two * self.x + T::Wide::from(3);
+        } else {
+          self.d = self.d + two * (self.x - self.y) + T::Wide::from(5);
+          self.y = self.y - one;
+        }
+        self.x = self.x + one;
+        self.reflection = 0;
+        self.done = self.x > self.y || self.x > self.limit;
+        continue;
+      }
+      let reflection = self.reflection;
+      self.reflection += 1;
+
+      // bit 2 swaps the axes, and bits 0 and 1 negate x and y. swapping
+      // on the diagonal, or negating zero, would repeat a pixel.
+      let (a, b) = if reflection & 4 != 0 {
+        if self.x == self.y {
+          continue;
+        }
+        (self.y, self.x)
+      } else {
+        (self.x, self.y)
+      };
+      if (reflection & 1 != 0 && a == zero) || (reflection & 2 != 0 && b == zero) {
+        continue;
+      }
+      let a = if reflection & 1 != 0 { -a } else { a };
+      let b = if reflection & 2 != 0 { -b } else { b };
+      // with no headroom in Wide, the pixel can be past its range as well
+      let x = self.cx.line_rs_checked_add(a).and_then(T::line_rs_narrow);
+      let y = self.cy.line_rs_checked_add(b).and_then(T::line_rs_narrow);
+      if let (Some(x), Some(y)) = (x, y) {
+        return Some(Point { x, y });
+      }
+    }
+    None
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for CircleIter<T> {}
+
+// the offset from `from`, which widens to `wide`, nearest to `offset` that
+// is still in range of T
+fn clamp_offset<T: LineRSInt>(from: T, wide: T::Wide, offset: T::Wide) -> T::Wide {
+  let end = narrow_clamped(wide.line_rs_saturating_add(offset), from);
+  end.line_rs_widen().map_or(T::Wide::from(0), |end| end - wide)
+}
+
+// how far the outline of a circle of radius r reaches either side of the
+// centre on the row dy away from it. the midpoint walk puts the pixel of
+// column x at the smallest y with (2y + 1)^2 >= 4(r^2 - x^2), which gives
+// the widest pixel of each row directly.
+fn half_width<W: LineRSWide>(r: W, dy: W) -> W {
+  let one = W::from(1);
+  let two = W::from(2);
+  let four = W::from(4);
+  let k = if dy < W::from(0) { -dy } else { dy };
+  // rows up to the diagonal hold a single pixel from the second octant
+  let y = ceil_sqrt(four * (r * r - k * k)) / two;
+  if y >= k {
+    return y;
+  }
+  // further out, the widest first octant column still at this row
+  let limit = four * r * r - (two * k - one) * (two * k - one);
+  (ceil_sqrt(limit) - one) / two
+}
+
+/// Lazily yields the filled disc of `radius` about `center` as horizontal
+/// spans, one per row from the top row down.
+///
+/// Each row reaches out to the outermost pixels `CircleIter` has on that
+/// row, so the disc covers the outline exactly. Only the rows in range of
+/// `T` are visited, and spans are cut short at its ends. The same limits on
+/// `radius` apply as for `CircleIter`.
+#[derive(Debug, Clone)]
+pub struct CircleSpanIter<T: LineRSInt> {
+  center: Point<T>,
+  cx: T::Wide,
+  cy: T::Wide,
+  radius: T::Wide,
+  dy: T::Wide,
+  dy_end: T::Wide,
+}
+
+impl<T: LineRSInt> CircleSpanIter<T> {
+  pub fn new(center: Point<T>, radius: u32) -> CircleSpanIter<T> {
+    let zero = T::Wide::from(0);
+    let radius = T::Wide::from(radius);
+    let widened = center.x.line_rs_widen().zip(center.y.line_rs_widen());
+    let (cx, cy) = widened.unwrap_or((zero, zero));
+    // a centre out of range of Wide has no rows
+    let (dy, dy_end) = if widened.is_some() {
+      (clamp_offset(center.y, cy, -radius), clamp_offset(center.y, cy, radius))
+    } else {
+      (T::Wide::from(1), zero)
+    };
+    CircleSpanIter { center, cx, cy, radius, dy, dy_end }
+  }
+}
+
+impl<T: LineRSInt> Iterator for CircleSpanIter<T> {
+  type Item = Span<T>;
+
+  fn next(&mut self) -> Option<Span<T>> {
+    while self.dy <= self.dy_end {
+      let dy = self.dy;
+      self.dy = self.dy + T::Wide::from(1);
+      let y = match self.cy.line_rs_checked_add(dy).and_then(T::line_rs_narrow) {
+        Some(y) => y,
+        None => continue,
+      };
+      let reach = half_width(self.radius, dy);
+      let cx = self.cx;
+      return Some(Span {
+        y,
+        x_start: narrow_clamped(cx.line_rs_saturating_add(-reach), self.center.x),
+        x_end: narrow_clamped(cx.line_rs_saturating_add(reach), self.center.x),
+      });
+    }
+    None
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for CircleSpanIter<T> {}
+
+/// All the pixels of `CircleIter::new(center, radius)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_circle<T: LineRSInt>(center: Point<T>, radius: u32) -> Vec<Point<T>> {
+  CircleIter::new(center, radius).collect()
+}
+
+/// All the spans of `CircleSpanIter::new(center, radius)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_filled_circle<T: LineRSInt>(center: Point<T>, radius: u32) -> Vec<Span<T>> {
+  CircleSpanIter::new(center, radius).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::calculate_circle;
+  use super::calculate_filled_circle;
+  use super::CircleIter;
+  use crate::bresenham::{Point, Span};
+  use crate::test_util::{sorted, sorted_distinct};
+
+  #[test]
+  fn small_circles() {
+    assert_eq!(calculate_circle(Point::new(4, -2), 0), vec![Point::new(4, -2)]);
+    assert_eq!(calculate_filled_circle(Point::new(4, -2), 0), vec![Span::new(-2, 4, 4)]);
+
+    let circle = calculate_circle(Point::new(0, 0), 2);
+    let expected = vec![
+      (-2, -1), (-2, 0), (-2, 1),
+      (-1, -2), (-1, 2),
+      (0, -2), (0, 2),
+      (1, -2), (1, 2),
+      (2, -1), (2, 0), (2, 1),
+    ];
+    assert_eq!(sorted(&circle), expected);
+    let disc = calculate_filled_circle(Point::new(0, 0), 2);
+    let expected = vec![
+      Span::new(-2, -1, 1),
+      Span::new(-1, -2, 2),
+      Span::new(0, -2, 2),
+      Span::new(1, -2, 2),
+      Span::new(2, -1, 1),
+    ];
+    assert_eq!(disc, expected);
+  }
+
+  #[test]
+  fn outline_is_symmetric_and_bounds_the_disc() {
+    for radius in 0..60 {
+      let circle = calculate_circle(Point::new(3, -5), radius);
+      let pixels = sorted_distinct(&circle);
+      for &(x, y) in &pixels {
+        let (x, y) = (x - 3, y + 5);
+        for &(a, b) in &[(-x, y), (x, -y), (y, x)] {
+          assert!(pixels.binary_search(&(a + 3, b - 5)).is_ok());
+        }
+      }
+
+      // each span runs between the outermost outline pixels of its row
+      let disc = calculate_filled_circle(Point::new(3, -5), radius);
+      assert_eq!(disc.len() as u32, 2 * radius + 1);
+      for &Span { y, x_start, x_end } in &disc {
+        let row = pixels.iter().filter(|p| p.1 == y).map(|p| p.0);
+        assert_eq!((row.clone().min(), row.max()), (Some(x_start), Some(x_end)), "radius {} row {}", radius, y);
+      }
+    }
+  }
+
+  #[test]
+  fn clipped_to_the_coordinate_range() {
+    let circle = calculate_circle(Point::new(0u8, 1), 3);
+    assert_eq!(circle.len(), 6);
+    assert!(circle.contains(&Point::new(3, 1)));
+    assert!(circle.contains(&Point::new(0, 4)));
+    let disc = calculate_filled_circle(Point::new(0u8, 1), 3);
+    let expected = vec![
+      Span::new(0, 0, 3),
+      Span::new(1, 0, 3),
+      Span::new(2, 0, 3),
+      Span::new(3, 0, 2),
+      Span::new(4, 0, 1),
+    ];
+    assert_eq!(disc, expected);
+  }
+
+  #[test]
+  fn walks_only_what_can_be_in_range() {
+    // the same pixels and spans as a wider type gives, with the walk
+    // started part way round wherever the top of the circle is out of range
+    let in_range = |v: i32| (-128..=127).contains(&v);
+    for &(cx, cy) in &[(0i8, 0i8), (127, 127), (-128, 5), (100, -128)] {
+      for radius in (0..400).step_by(3) {
+        let center = Point::new(cx as i32, cy as i32);
+        let expected: Vec<_> = sorted(&calculate_circle(center, radius))
+          .into_iter()
+          .filter(|&(x, y)| in_range(x) && in_range(y))
+          .collect();
+        let circle = calculate_circle(Point::new(cx, cy), radius);
+        let pixels: Vec<_> = sorted(&circle).into_iter().map(|(x, y)| (x as i32, y as i32)).collect();
+        assert_eq!(pixels, expected, "({}, {}) radius {}", cx, cy, radius);
+
+        let expected: Vec<_> = calculate_filled_circle(center, radius)
+          .into_iter()
+          .filter(|span| in_range(span.y))
+          .map(|span| Span::new(span.y as i8, span.x_start.max(-128) as i8, span.x_end.min(127) as i8))
+          .collect();
+        assert_eq!(calculate_filled_circle(Point::new(cx, cy), radius), expected);
+      }
+    }
+
+    // a radius far beyond the range leaves no outline in it, and a disc
+    // covering all of it, without walking the rows and columns it can't
+    // reach
+    assert_eq!(CircleIter::new(Point::new(0i8, 0), 2_000_000_000).next(), None);
+    let disc = calculate_filled_circle(Point::new(0i8, 0), 2_000_000_000);
+    assert_eq!(disc, (-128..=127).map(|y| Span::new(y, -128, 127)).collect::<Vec<_>>());
+    assert_eq!(CircleIter::new(Point::new(255u8, 0), u32::MAX).next(), None);
+    let disc = calculate_filled_circle(Point::new(255u8, 0), u32::MAX);
+    assert_eq!(disc, (0..=255).map(|y| Span::new(y, 0, 255)).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn skipped_at_the_ends_of_wide() {
+    // i128 is its own Wide, so the offsets have no headroom
+    let circle = calculate_circle(Point::new(i128::MAX - 1, 10), 2);
+    let pixels: Vec<_> = sorted(&circle).into_iter().map(|(x, y)| (x - (i128::MAX - 1), y - 10)).collect();
+    let expected = vec![
+      (-2, -1), (-2, 0), (-2, 1),
+      (-1, -2), (-1, 2),
+      (0, -2), (0, 2),
+      (1, -2), (1, 2),
+    ];
+    assert_eq!(pixels, expected);
+    let disc = calculate_filled_circle(Point::new(i128::MIN, i128::MAX), 1);
+    assert_eq!(disc, vec![Span::new(i128::MAX - 1, i128::MIN, i128::MIN), Span::new(i128::MAX, i128::MIN, i128::MIN + 1)]);
+
+    // and u128 coordinates past i128::MAX don't fit in it at all
+    let far = Point::new(u128::MAX - 5, 10);
+    assert_eq!(calculate_circle(far, 2), vec![]);
+    assert_eq!(calculate_filled_circle(far, 2), vec![]);
+    assert_eq!(calculate_circle(Point::new(10u128, 10), 1).len(), 4);
+    assert_eq!(calculate_filled_circle(Point::new(10u128, 10), 1).len(), 3);
+  }
+}
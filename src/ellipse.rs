@@ -0,0 +1,627 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{LineRSInt, LineRSWide, Point, Rect, Span};
+#[cfg(feature = "std")]
+use crate::bresenham::{narrow_clamped, LineRSUint};
+
+// an ellipse through pixel centres, in coordinates doubled so that its
+// centre is always a whole number, and taken relative to a pixel next to it
+// so that they stay small wherever the ellipse is
+#[derive(Debug, Clone)]
+pub(crate) struct EllipseShape<T: LineRSInt> {
+  // the pixel the rest is relative to
+  pub(crate) x0: T::Wide,
+  pub(crate) y0: T::Wide,
+  // twice the centre, and the width and height between the outer pixel
+  // centres
+  pub(crate) cx2: T::Wide,
+  pub(crate) cy2: T::Wide,
+  pub(crate) w: T::Wide,
+  pub(crate) h: T::Wide,
+  // w and h divided by their greatest common divisor
+  aspect: (T::Wide, T::Wide),
+}
+
+impl<T: LineRSInt> EllipseShape<T> {
+  // these are None when a coordinate is out of range of Wide, or the shape
+  // is empty or too big for it
+  pub(crate) fn inscribed(rect: Rect<T>) -> Option<EllipseShape<T>> {
+    let (x0, x1) = (rect.min.x.line_rs_widen()?, rect.max.x.line_rs_widen()?);
+    let (y0, y1) = (rect.min.y.line_rs_widen()?, rect.max.y.line_rs_widen()?);
+    let (w, h) = (x1.line_rs_checked_sub(x0)?, y1.line_rs_checked_sub(y0)?);
+    EllipseShape::sized((x0, y0), (w, h), w, h)
+  }
+
+  pub(crate) fn circle(center: Point<T>, radius: u32) -> Option<EllipseShape<T>> {
+    let zero = T::Wide::from(0);
+    let diameter = T::Wide::from(2) * T::Wide::from(radius);
+    let center = (center.x.line_rs_widen()?, center.y.line_rs_widen()?);
+    EllipseShape::sized(center, (zero, zero), diameter, diameter)
+  }
+
+  fn sized(
+    (x0, y0): (T::Wide, T::Wide),
+    (cx2, cy2): (T::Wide, T::Wide),
+    w: T::Wide,
+    h: T::Wide,
+  ) -> Option<EllipseShape<T>> {
+    let zero = T::Wide::from(0);
+    let one = T::Wide::from(1);
+    let two = T::Wide::from(2);
+    if w < zero || h < zero {
+      return None;
+    }
+    let divisor = gcd(w, h);
+    let aspect = if divisor == zero { (zero, zero) } else { (w / divisor, h / divisor) };
+    // reach works with (aspect.0 * h)^2 at most, and the spans with twice
+    // the sides
+    let widest = aspect.0.max(one).line_rs_checked_mul(h)?;
+    widest.line_rs_checked_mul(widest)?;
+    two.line_rs_checked_mul(w.max(h).line_rs_checked_add(one)?)?;
+    Some(EllipseShape { x0, y0, cx2, cy2, w, h, aspect })
+  }
+
+  // a single pixel at the origin, standing in for a shape that was None
+  pub(crate) fn point() -> EllipseShape<T> {
+    let zero = T::Wide::from(0);
+    EllipseShape { x0: zero, y0: zero, cx2: zero, cy2: zero, w: zero, h: zero, aspect: (zero, zero) }
+  }
+
+  // how far the span of the row at q (twice its offset from the centre,
+  // taken positive, and at most h) reaches either side of the centre,
+  // also doubled
+  pub(crate) fn reach(&self, q: T::Wide) -> T::Wide {
+    let zero = T::Wide::from(0);
+    let one = T::Wide::from(1);
+    let (w, h) = (self.w, self.h);
+    if h == zero {
+      return w;
+    }
+    // with m twice the offset of a pixel from the centre, the horizontal
+    // line through it reaches the ellipse when
+    // (m - 1)^2 h^2 + q^2 w^2 <= w^2 h^2, and the vertical line when
+    // m^2 h^2 + (q - 1)^2 w^2 <= w^2 h^2. only the ratio of w to h
+    // matters there, so it is taken in lowest terms.
+    let (a, b) = self.aspect;
+    let horizontal = one + (a * a * (h * h - q * q)).line_rs_isqrt() / b;
+    let q_inner = if q > zero { q - one } else { zero };
+    let vertical = (a * a * (h * h - q_inner * q_inner)).line_rs_isqrt() / b;
+    let m = horizontal.max(vertical);
+    // only offsets of the same parity as the doubled centre land on pixels
+    if (m - self.cx2) % T::Wide::from(2) == zero { m } else { m - one }
+  }
+}
+
+fn gcd<W: LineRSWide>(mut a: W, mut b: W) -> W {
+  while b != W::from(0) {
+    let rest = a % b;
+    a = b;
+    b = rest;
+  }
+  a
+}
+
+/// Lazily yields the filled ellipse inscribed in `rect` as horizontal spans,
+/// one per row from the top row down.
+///
+/// The ellipse passes through the centres of the pixels midway along each
+/// side of `rect`, which is a half-pixel position on sides of even length.
+/// A pixel is filled when the horizontal or vertical line through its
+/// centre, a pixel long, reaches the ellipse. For a square `rect` this is
+/// exactly the disc of `CircleSpanIter`, and every row and column of `rect`
+/// is reached whatever its size.
+///
+/// The arithmetic is done in `LineRSInt::Wide`, relative to the corner of
+/// `rect`, and needs the squared area of `rect` divided by the square of
+/// the greatest common divisor of its sides to fit there, so a square
+/// `rect` only needs the square of its side. A bigger `rect` has no
+/// ellipse, and nor does an empty one.
+#[derive(Debug, Clone)]
+pub struct EllipseSpanIter<T: LineRSInt> {
+  shape: EllipseShape<T>,
+  x0: T,
+  x1: T,
+  y: T,
+  y1: T,
+  done: bool,
+}
+
+impl<T: LineRSInt> EllipseSpanIter<T> {
+  pub fn new(rect: Rect<T>) -> EllipseSpanIter<T> {
+    let shape = EllipseShape::inscribed(rect);
+    EllipseSpanIter {
+      done: shape.is_none(),
+      shape: shape.unwrap_or_else(EllipseShape::point),
+      x0: rect.min.x,
+      x1: rect.max.x,
+      y: rect.min.y,
+      y1: rect.max.y,
+    }
+  }
+}
+
+impl<T: LineRSInt> Iterator for EllipseSpanIter<T> {
+  type Item = Span<T>;
+
+  fn next(&mut self) -> Option<Span<T>> {
+    if self.done {
+      return None;
+    }
+    let y = self.y;
+    if y == self.y1 {
+      self.done = true;
+    } else {
+      self.y = y + T::line_rs_one();
+    }
+    let two = T::Wide::from(2);
+    let shape = &self.shape;
+    // y lies within rect, which widened, so it does too
+    let q = two * (y.line_rs_widen()? - shape.y0) - shape.cy2;
+    let m = shape.reach(if q < T::Wide::from(0) { -q } else { q });
+    // the span never leaves the rectangle, so these always narrow
+    let start = T::line_rs_narrow(shape.x0 + (shape.cx2 - m) / two).unwrap_or(self.x0);
+    let end = T::line_rs_narrow(shape.x0 + (shape.cx2 + m) / two).unwrap_or(self.x1);
+    Some(Span { y, x_start: start, x_end: end })
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for EllipseSpanIter<T> {}
+
+// the pixels of a filled shape, given as one span per row, that have a
+// pixel above, below, left or right of them outside the shape
+#[derive(Debug, Clone)]
+struct SpanOutline<T: LineRSInt, I: Iterator<Item = Span<T>>> {
+  spans: I,
+  prev: Option<Span<T>>,
+  cur: Option<Span<T>>,
+  next: Option<Span<T>>,
+  // the pixels of the current row strictly inside the shape, which are
+  // skipped, and the next x to yield
+  interior: Option<(T, T)>,
+  x: Option<T>,
+}
+
+impl<T: LineRSInt, I: Iterator<Item = Span<T>>> SpanOutline<T, I> {
+  fn new(mut spans: I) -> SpanOutline<T, I> {
+    let cur = spans.next();
+    let next = spans.next();
+    let mut outline = SpanOutline { spans, prev: None, cur, next, interior: None, x: None };
+    outline.begin_row();
+    outline
+  }
+
+  fn begin_row(&mut self) {
+    let Span { y, x_start: start, x_end: end } = match self.cur {
+      Some(span) => span,
+      None => return,
+    };
+    let one = T::line_rs_one();
+    let adjacent = |a: T, b: T| a < b && a + one == b;
+    let above = self.prev.filter(|span| adjacent(span.y, y));
+    let below = self.next.filter(|span| adjacent(y, span.y));
+    self.interior = match (above, below) {
+      (Some(above), Some(below)) if start < end => {
+        let lo = max(max(start + one, above.x_start), below.x_start);
+        let hi = min(min(end - one, above.x_end), below.x_end);
+        if lo <= hi { Some((lo, hi)) } else { None }
+      },
+      _ => None,
+    };
+    self.x = Some(start);
+  }
+}
+
+fn max<T: PartialOrd>(a: T, b: T) -> T {
+  if a < b { b } else { a }
+}
+
+fn min<T: PartialOrd>(a: T, b: T) -> T {
+  if b < a { b } else { a }
+}
+
+impl<T: LineRSInt, I: Iterator<Item = Span<T>>> Iterator for SpanOutline<T, I> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    loop {
+      let Span { y, x_end: end, .. } = self.cur?;
+      if let Some(x) = self.x {
+        self.x = if x == end {
+          None
+        } else {
+          match self.interior {
+            Some((lo, hi)) if x + T::line_rs_one() == lo => Some(hi + T::line_rs_one()),
+            _ => Some(x + T::line_rs_one()),
+          }
+        };
+        return Some(Point { x, y });
+      }
+      self.prev = self.cur;
+      self.cur = self.next;
+      self.next = self.spans.next();
+      self.begin_row();
+    }
+  }
+}
+
+/// Lazily yields the outline of the ellipse inscribed in `rect`: the pixels
+/// of `EllipseSpanIter` with an edge on the outside of the filled ellipse,
+/// row by row from the top, each exactly once.
+#[derive(Debug, Clone)]
+pub struct EllipseIter<T: LineRSInt> {
+  outline: SpanOutline<T, EllipseSpanIter<T>>,
+}
+
+impl<T: LineRSInt> EllipseIter<T> {
+  pub fn new(rect: Rect<T>) -> EllipseIter<T> {
+    EllipseIter { outline: SpanOutline::new(EllipseSpanIter::new(rect)) }
+  }
+}
+
+impl<T: LineRSInt> Iterator for EllipseIter<T> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    self.outline.next()
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for EllipseIter<T> {}
+
+// v as a Wide, for the offsets of a rotated ellipse from its rectangle
+#[cfg(feature = "std")]
+fn wide_from_i64<W: LineRSWide>(v: i64) -> W {
+  let magnitude = v.unsigned_abs();
+  let high = W::from((magnitude >> 32) as u32) * W::from(1 << 16) * W::from(1 << 16);
+  let wide = high + W::from(magnitude as u32);
+  if v < 0 { -wide } else { wide }
+}
+
+/// Lazily yields the filled ellipse inscribed in `rect`, rotated about the
+/// centre of `rect` by `angle` radians (from the x axis towards the y
+/// axis), as horizontal spans from the top row down.
+///
+/// Pixels are filled by the same rule as `EllipseSpanIter`, which an angle
+/// of zero reproduces exactly; the rotated shape is worked out in floating
+/// point. Rows outside the range of `T` are skipped and spans are cut short
+/// at its ends.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct RotatedEllipseSpanIter<T: LineRSInt> {
+  x0: T,
+  y0: T,
+  // centre relative to the pixel at the rectangle's minimum corner
+  cx: f64,
+  cy: f64,
+  // the ellipse, relative to its centre, is p x^2 + q x y + r y^2 <= k
+  p: f64,
+  q: f64,
+  r: f64,
+  k: f64,
+  // the height at which the ellipse reaches furthest right; furthest left
+  // is at minus this
+  y_right: f64,
+  v: i64,
+  v_end: i64,
+}
+
+#[cfg(feature = "std")]
+impl<T: LineRSInt> RotatedEllipseSpanIter<T> {
+  pub fn new(rect: Rect<T>, angle: f64) -> RotatedEllipseSpanIter<T> {
+    // everything is worked out relative to the minimum corner, which has to
+    // widen for the rows and columns to be placed
+    let fits = rect.min.x.line_rs_widen().is_some() && rect.min.y.line_rs_widen().is_some();
+    let empty = !fits || rect.min.x > rect.max.x || rect.min.y > rect.max.y;
+    let size = |lo: T, hi: T| hi.line_rs_abs_diff(lo).line_rs_to_usize() as f64;
+    let (a, b) = if empty {
+      (0.0, 0.0)
+    } else {
+      (size(rect.min.x, rect.max.x) / 2.0, size(rect.min.y, rect.max.y) / 2.0)
+    };
+    let (sin, cos) = angle.sin_cos();
+    let p = b * b * cos * cos + a * a * sin * sin;
+    let q = 2.0 * cos * sin * (b * b - a * a);
+    let r = b * b * sin * sin + a * a * cos * cos;
+    let k = a * a * b * b;
+    let spread = (q * q + 4.0 * k).sqrt();
+    let y_right = if spread > 0.0 { -q * p.sqrt() / spread } else { 0.0 };
+    // the rows a pixel's vertical line can reach the ellipse from
+    let reach = p.sqrt() + 0.5;
+    // the centre is the semi-axes in from the rectangle's minimum corner
+    let (cx, cy) = (a, b);
+    RotatedEllipseSpanIter {
+      x0: rect.min.x,
+      y0: rect.min.y,
+      cx,
+      cy,
+      p,
+      q,
+      r,
+      k,
+      y_right,
+      v: if empty { 1 } else { (cy - reach).ceil() as i64 },
+      v_end: if empty { 0 } else { (cy + reach).floor() as i64 },
+    }
+  }
+
+  // the ellipse's left and right ends at height y from its centre
+  fn chord(&self, y: f64) -> Option<(f64, f64)> {
+    if self.p <= 0.0 {
+      // flattened onto the x axis
+      let half = self.r.sqrt();
+      return if y == 0.0 { Some((-half, half)) } else { None };
+    }
+    let room = self.p - y * y;
+    if room < 0.0 {
+      return None;
+    }
+    let spread = 2.0 * self.k.sqrt() * room.sqrt();
+    Some(((-self.q * y - spread) / (2.0 * self.p), (-self.q * y + spread) / (2.0 * self.p)))
+  }
+
+  // the furthest left and right the ellipse reaches between heights lo and
+  // hi from its centre
+  fn band(&self, lo: f64, hi: f64) -> Option<(f64, f64)> {
+    if self.p <= 0.0 {
+      return if lo <= 0.0 && 0.0 <= hi { self.chord(0.0) } else { None };
+    }
+    let limit = self.p.sqrt();
+    let (lo, hi) = (lo.max(-limit), hi.min(limit));
+    if lo > hi {
+      return None;
+    }
+    let left = self.chord((-self.y_right).max(lo).min(hi))?.0;
+    let right = self.chord(self.y_right.max(lo).min(hi))?.1;
+    Some((left, right))
+  }
+}
+
+#[cfg(feature = "std")]
+impl<T: LineRSInt> Iterator for RotatedEllipseSpanIter<T> {
+  type Item = Span<T>;
+
+  fn next(&mut self) -> Option<Span<T>> {
+    while self.v <= self.v_end {
+      let v = self.v;
+      self.v += 1;
+      let y = v as f64 - self.cy;
+      let horizontal = self.chord(y)
+        .map(|(left, right)| ((self.cx + left - 0.5).ceil(), (self.cx + right + 0.5).floor()));
+      let vertical = self.band(y - 0.5, y + 0.5)
+        .map(|(left, right)| ((self.cx + left).ceil(), (self.cx + right).floor()));
+      let (start, end) = match (horizontal, vertical) {
+        (Some(h), Some(v)) => (h.0.min(v.0), h.1.max(v.1)),
+        (Some(span), None) | (None, Some(span)) => span,
+        (None, None) => continue,
+      };
+      if start > end {
+        continue;
+      }
+      let row = match self.y0.line_rs_widen()?.line_rs_checked_add(wide_from_i64(v)).and_then(T::line_rs_narrow) {
+        Some(row) => row,
+        None => continue,
+      };
+      let x0 = self.x0.line_rs_widen()?;
+      let (start, end) = (wide_from_i64::<T::Wide>(start as i64), wide_from_i64::<T::Wide>(end as i64));
+      // a span entirely past one end of the range of T, or of Wide, has
+      // nothing to show
+      let narrow = |offset| x0.line_rs_checked_add(offset).and_then(T::line_rs_narrow);
+      let zero = T::Wide::from(0);
+      if narrow(start).is_none() && narrow(end).is_none() && (start > zero) == (end > zero) {
+        continue;
+      }
+      return Some(Span {
+        y: row,
+        x_start: narrow_clamped(x0.line_rs_saturating_add(start), self.x0),
+        x_end: narrow_clamped(x0.line_rs_saturating_add(end), self.x0),
+      });
+    }
+    None
+  }
+}
+
+#[cfg(feature = "std")]
+impl<T: LineRSInt> FusedIterator for RotatedEllipseSpanIter<T> {}
+
+/// Lazily yields the outline of the rotated ellipse of
+/// `RotatedEllipseSpanIter`, row by row from the top, each pixel exactly
+/// once.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct RotatedEllipseIter<T: LineRSInt> {
+  outline: SpanOutline<T, RotatedEllipseSpanIter<T>>,
+}
+
+#[cfg(feature = "std")]
+impl<T: LineRSInt> RotatedEllipseIter<T> {
+  pub fn new(rect: Rect<T>, angle: f64) -> RotatedEllipseIter<T> {
+    RotatedEllipseIter { outline: SpanOutline::new(RotatedEllipseSpanIter::new(rect, angle)) }
+  }
+}
+
+#[cfg(feature = "std")]
+impl<T: LineRSInt> Iterator for RotatedEllipseIter<T> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    self.outline.next()
+  }
+}
+
+#[cfg(feature = "std")]
+impl<T: LineRSInt> FusedIterator for RotatedEllipseIter<T> {}
+
+/// All the pixels of `EllipseIter::new(rect)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_ellipse<T: LineRSInt>(rect: Rect<T>) -> Vec<Point<T>> {
+  EllipseIter::new(rect).collect()
+}
+
+/// All the spans of `EllipseSpanIter::new(rect)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_filled_ellipse<T: LineRSInt>(rect: Rect<T>) -> Vec<Span<T>> {
+  EllipseSpanIter::new(rect).collect()
+}
+
+/// All the pixels of `RotatedEllipseIter::new(rect, angle)`.
+#[cfg(feature = "std")]
+pub fn calculate_rotated_ellipse<T: LineRSInt>(rect: Rect<T>, angle: f64) -> Vec<Point<T>> {
+  RotatedEllipseIter::new(rect, angle).collect()
+}
+
+/// All the spans of `RotatedEllipseSpanIter::new(rect, angle)`.
+#[cfg(feature = "std")]
+pub fn calculate_filled_rotated_ellipse<T: LineRSInt>(rect: Rect<T>, angle: f64) -> Vec<Span<T>> {
+  RotatedEllipseSpanIter::new(rect, angle).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::calculate_ellipse;
+  use super::calculate_filled_ellipse;
+  use crate::bresenham::{Point, Rect, Span};
+  use crate::circle::calculate_filled_circle;
+  use crate::test_util::sorted_distinct;
+
+  #[test]
+  fn square_rect_is_the_circle() {
+    for radius in 0..40 {
+      let rect = Rect::new(Point::new(5 - radius, -radius), Point::new(5 + radius, radius));
+      assert_eq!(calculate_filled_ellipse(rect), calculate_filled_circle(Point::new(5, 0), radius as u32));
+    }
+  }
+
+  #[test]
+  fn fills_the_rect_for_even_and_odd_sizes() {
+    for w in 0..14 {
+      for h in 0..14 {
+        let rect = Rect::new(Point::new(-3, 2), Point::new(-3 + w, 2 + h));
+        let spans = calculate_filled_ellipse(rect);
+        let rows: Vec<_> = spans.iter().map(|s| s.y).collect();
+        assert_eq!(rows, (2..=2 + h).collect::<Vec<_>>());
+        assert_eq!(spans.iter().map(|s| s.x_start).min(), Some(-3));
+        assert_eq!(spans.iter().map(|s| s.x_end).max(), Some(-3 + w));
+        for (i, span) in spans.iter().enumerate() {
+          // symmetric across both axes of the rectangle
+          assert_eq!(span.x_start - -3, -3 + w - span.x_end, "{}x{}", w, h);
+          let mirrored = spans[spans.len() - 1 - i];
+          assert_eq!((span.x_start, span.x_end), (mirrored.x_start, mirrored.x_end));
+        }
+
+        // the outline is every pixel on the edge of the fill, once each,
+        // and hangs together
+        let outline = calculate_ellipse(rect);
+        let pixels = sorted_distinct(&outline);
+        let filled = |x: i32, y: i32| spans.iter().any(|s| s.y == y && s.x_start <= x && x <= s.x_end);
+        for &Span { y, x_start, x_end } in &spans {
+          for x in x_start..=x_end {
+            let edge = !filled(x - 1, y) || !filled(x + 1, y) || !filled(x, y - 1) || !filled(x, y + 1);
+            assert_eq!(pixels.binary_search(&(x, y)).is_ok(), edge);
+          }
+        }
+        if pixels.len() > 1 {
+          for &(x, y) in &pixels {
+            assert!(pixels.iter().any(|&(a, b)| (a, b) != (x, y) && (a - x).abs() <= 1 && (b - y).abs() <= 1));
+          }
+        }
+      }
+    }
+    assert!(calculate_filled_ellipse(Rect::new(Point::new(1, 0), Point::new(0, 5))).is_empty());
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn rotated() {
+    use super::{calculate_filled_rotated_ellipse, calculate_rotated_ellipse};
+
+    for w in 0..12 {
+      for h in 0..12 {
+        let rect = Rect::new(Point::new(-3, 2), Point::new(-3 + w, 2 + h));
+        assert_eq!(calculate_filled_rotated_ellipse(rect, 0.0), calculate_filled_ellipse(rect));
+        assert_eq!(calculate_rotated_ellipse(rect, 0.0), calculate_ellipse(rect));
+      }
+    }
+    // a circle looks the same at any angle
+    let rect = Rect::new(Point::new(-9, -9), Point::new(9, 9));
+    assert_eq!(calculate_filled_rotated_ellipse(rect, 0.7), calculate_filled_ellipse(rect));
+
+    // a long thin ellipse at 45 degrees runs along the diagonal
+    let rect = Rect::new(Point::new(-10, -1), Point::new(10, 1));
+    let spans = calculate_filled_rotated_ellipse(rect, core::f64::consts::FRAC_PI_4);
+    assert_eq!(spans.first().map(|s| s.y), Some(-7));
+    assert_eq!(spans.last().map(|s| s.y), Some(7));
+    for &Span { y, x_start: start, x_end: end } in &spans {
+      assert!(start <= y && y <= end && end - start <= 4, "row {}: {}..{}", y, start, end);
+    }
+
+    // cut short at the ends of the range
+    let spans = calculate_filled_rotated_ellipse(Rect::new(Point::new(0u8, 0), Point::new(2, 12)), 0.8);
+    let wide = calculate_filled_rotated_ellipse(Rect::new(Point::new(0i32, 0), Point::new(2, 12)), 0.8);
+    assert!(wide.iter().any(|s| s.x_start < 0));
+    let expected: Vec<_> = wide.iter()
+      .filter(|s| s.y >= 0 && s.x_end >= 0)
+      .map(|s| Span::new(s.y as u8, s.x_start.max(0) as u8, s.x_end as u8))
+      .collect();
+    assert_eq!(spans, expected);
+    // and at the ends of Wide, for a type that is its own Wide
+    let corner = i128::MAX - 3;
+    let spans = calculate_filled_rotated_ellipse(Rect::new(Point::new(corner, corner - 9), Point::new(corner + 2, corner + 3)), 0.8);
+    let expected: Vec<_> = wide.iter()
+      .filter(|s| s.y <= 12 && s.x_start <= 3)
+      .map(|s| Span::new(corner - 9 + s.y as i128, corner + s.x_start as i128, corner + s.x_end.min(3) as i128))
+      .collect();
+    assert_eq!(spans, expected);
+  }
+
+  #[test]
+  fn full_range_without_overflow() {
+    let spans = calculate_filled_ellipse(Rect::new(Point::new(0u16, 0), Point::new(60000, 60000)));
+    assert_eq!(spans.len(), 60001);
+    assert_eq!(spans[30000], Span::new(30000, 0, 60000));
+    assert_eq!(spans[0], Span::new(0, spans[0].x_start, 60000 - spans[0].x_start));
+    let spans = calculate_filled_ellipse(Rect::new(Point::new(i16::MIN, i16::MIN), Point::new(i16::MAX, i16::MAX)));
+    assert_eq!(spans.len(), 65536);
+  }
+
+  #[test]
+  fn small_rects_at_the_ends_of_wide() {
+    let near = calculate_filled_ellipse(Rect::new(Point::new(0i128, 0), Point::new(4, 4)));
+    let outline = calculate_ellipse(Rect::new(Point::new(0i128, 0), Point::new(4, 4)));
+    let (max, min) = (i128::MAX - 4, i128::MIN);
+    for &(x, y) in &[(max, 0), (min, min), (min, max), (max, max)] {
+      let rect = Rect::new(Point::new(x, y), Point::new(x + 4, y + 4));
+      let moved: Vec<_> = near.iter().map(|s| Span::new(s.y + y, s.x_start + x, s.x_end + x)).collect();
+      assert_eq!(calculate_filled_ellipse(rect), moved);
+      let moved: Vec<_> = outline.iter().map(|p| Point::new(p.x + x, p.y + y)).collect();
+      assert_eq!(calculate_ellipse(rect), moved);
+    }
+    let far = Rect::new(Point::new(u128::MAX - 4, 0), Point::new(u128::MAX, 4));
+    assert_eq!(calculate_filled_ellipse(far), vec![]);
+    assert_eq!(calculate_ellipse(far), vec![]);
+  }
+
+  #[test]
+  fn big_rects_within_the_limits_of_wide() {
+    use super::EllipseSpanIter;
+    // a square only needs the square of its side to fit
+    let r = 1i64 << 61;
+    let half = (4 * i128::from(r) - 1).isqrt() as i64 / 2;
+    let mut square = EllipseSpanIter::new(Rect::new(Point::new(0i64, 0), Point::new(2 * r, 2 * r)));
+    assert_eq!(square.next(), Some(Span::new(0, r - half, r + half)));
+    let mut square = EllipseSpanIter::new(Rect::new(Point::new(0i64, 0), Point::new(1 << 34, 1 << 34)));
+    assert_eq!(square.next().map(|s| s.x_start + s.x_end), Some(1 << 34));
+    // other rects need their squared area over that of the common divisor
+    // of their sides
+    let mut wide = EllipseSpanIter::new(Rect::new(Point::new(0i64, 0), Point::new(3 << 60, 1 << 61)));
+    assert_eq!(wide.next().map(|s| (s.y, s.x_start + s.x_end)), Some((0, 3 << 60)));
+    let coprime = Rect::new(Point::new(0i64, 0), Point::new(1 << 34, (1 << 34) - 1));
+    assert_eq!(EllipseSpanIter::new(coprime).next(), None);
+    // and the sides themselves must fit
+    let flat = Rect::new(Point::new(i128::MIN, 0), Point::new(i128::MAX, 0));
+    assert_eq!(calculate_filled_ellipse(flat), vec![]);
+    let flat = Rect::new(Point::new(i64::MIN, 0), Point::new(i64::MAX, 0));
+    assert_eq!(calculate_filled_ellipse(flat), vec![Span::new(0, i64::MIN, i64::MAX)]);
+  }
+}
@@ -0,0 +1,166 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{LineRSInt, LineRSUint, Point};
+use crate::supercover::{GridStep, GridWalk};
+
+/// Which axis a 4-connected line steps along first when the ideal segment
+/// passes exactly through a cell corner, leaving both orders equally close.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AxisOrder {
+  XFirst,
+  YFirst,
+}
+
+/// Lazily yields a path of orthogonal unit steps from `p1` to `p2` that
+/// stays as close as possible to the ideal segment, for movement rules that
+/// forbid diagonal moves.
+///
+/// Every cell on the path is one the segment passes through, so away from
+/// exact corner crossings the path matches `SupercoverIter`; at a corner
+/// the step is split in two in the order given by `order`. The path always
+/// has `|dx| + |dy| + 1` cells; past `usize::MAX` of them, `size_hint` is
+/// `(usize::MAX, None)` and `len` panics.
+#[derive(Debug, Clone)]
+pub struct FourConnectedIter<T: LineRSInt> {
+  walk: GridWalk<T>,
+  order: AxisOrder,
+  between: Option<Point<T>>,
+  done: bool,
+}
+
+impl<T: LineRSInt> FourConnectedIter<T> {
+  pub fn new(p1: Point<T>, p2: Point<T>, order: AxisOrder) -> FourConnectedIter<T> {
+    FourConnectedIter {
+      walk: GridWalk::new(p1, p2),
+      order,
+      between: None,
+      done: false,
+    }
+  }
+}
+
+impl<T: LineRSInt> Iterator for FourConnectedIter<T> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    if let Some(point) = self.between.take() {
+      return Some(point);
+    }
+    if self.done {
+      return None;
+    }
+    let point = self.walk.position();
+    match self.walk.next_step() {
+      None => self.done = true,
+      Some(step) => {
+        if step == GridStep::Corner {
+          self.between = Some(self.walk.beside(match self.order {
+            AxisOrder::XFirst => GridStep::X,
+            AxisOrder::YFirst => GridStep::Y,
+          }));
+        }
+        self.walk.take(step);
+      },
+    }
+    Some(point)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let between = if self.between.is_some() { 1 } else { 0 };
+    if self.done {
+      return (between, Some(between));
+    }
+    let (x_left, y_left) = self.walk.steps_left();
+    let len = x_left.line_rs_to_usize()
+      .checked_add(y_left.line_rs_to_usize())
+      .and_then(|len| len.checked_add(1 + between));
+    match len {
+      Some(len) => (len, Some(len)),
+      None => (usize::MAX, None),
+    }
+  }
+}
+
+impl<T: LineRSInt> ExactSizeIterator for FourConnectedIter<T> {}
+
+impl<T: LineRSInt> FusedIterator for FourConnectedIter<T> {}
+
+/// All the cells of `FourConnectedIter::new(p1, p2, order)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_four_connected_line<T: LineRSInt>(
+  p1: Point<T>,
+  p2: Point<T>,
+  order: AxisOrder,
+) -> Vec<Point<T>> {
+  FourConnectedIter::new(p1, p2, order).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::calculate_four_connected_line;
+  use super::AxisOrder;
+  use super::FourConnectedIter;
+  use crate::bresenham::Point;
+  use crate::supercover::{calculate_supercover_line, CornerPolicy};
+
+  #[test]
+  fn corner_ties_follow_axis_order() {
+    let x_first = calculate_four_connected_line(Point::new(0, 0), Point::new(2, 2), AxisOrder::XFirst);
+    let expected = vec![
+      Point::new(0, 0),
+      Point::new(1, 0),
+      Point::new(1, 1),
+      Point::new(2, 1),
+      Point::new(2, 2),
+    ];
+    assert_eq!(x_first, expected);
+    let y_first = calculate_four_connected_line(Point::new(0, 0), Point::new(2, 2), AxisOrder::YFirst);
+    let expected = vec![
+      Point::new(0, 0),
+      Point::new(0, 1),
+      Point::new(1, 1),
+      Point::new(1, 2),
+      Point::new(2, 2),
+    ];
+    assert_eq!(y_first, expected);
+  }
+
+  #[test]
+  fn orthogonal_steps_along_the_segment() {
+    let ends = [(0i32, 0i32), (7, 3), (-2, 5), (-6, -6), (4, -1), (0, -5), (3, 0)];
+    for &(x1, y1) in &ends {
+      for &(x2, y2) in &ends {
+        for &order in &[AxisOrder::XFirst, AxisOrder::YFirst] {
+          let a = Point::new(x1, y1);
+          let b = Point::new(x2, y2);
+          let iter = FourConnectedIter::new(a, b, order);
+          let expected_len = ((x2 - x1).abs() + (y2 - y1).abs() + 1) as usize;
+          assert_eq!(iter.len(), expected_len);
+          let path: Vec<_> = iter.collect();
+          assert_eq!(path.len(), expected_len);
+          assert_eq!(path[0], a);
+          assert_eq!(path[path.len() - 1], b);
+          for pair in path.windows(2) {
+            assert_eq!((pair[1].x - pair[0].x).abs() + (pair[1].y - pair[0].y).abs(), 1);
+          }
+          let touched = calculate_supercover_line(a, b, CornerPolicy::Both);
+          assert!(path.iter().all(|p| touched.contains(p)));
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn size_hint_past_usize_max() {
+    let iter = FourConnectedIter::new(Point::new(0u128, 0), Point::new(usize::MAX as u128, 1), AxisOrder::XFirst);
+    assert_eq!(iter.size_hint(), (usize::MAX, None));
+  }
+
+  #[test]
+  #[should_panic]
+  fn len_panics_past_usize_max() {
+    FourConnectedIter::new(Point::new(0u128, 0), Point::new(usize::MAX as u128, 1), AxisOrder::XFirst).len();
+  }
+}
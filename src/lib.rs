@@ -1 +1,25 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod arc;
+pub mod bezier;
 pub mod bresenham;
+pub mod circle;
+pub mod ellipse;
+pub mod four_connected;
+pub mod line3d;
+pub mod line_nd;
+#[cfg(feature = "alloc")]
+pub mod polygon;
+pub mod polyline;
+pub mod raycast;
+pub mod runs;
+pub mod supercover;
+pub mod thick;
+pub mod triangle;
+pub mod wu;
+
+#[cfg(all(test, feature = "alloc"))]
+mod test_util;
@@ -0,0 +1,197 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{offset, LineRSInt, LineRSUint, Point3, Sign, SignedInt};
+
+// an axis moving no further than the major axis, with its own
+// halved bresenham error term (see BresenhamIter::with_tie_break). ties
+// step the minor axis, as in the classic 2d line.
+#[derive(Debug, Clone)]
+pub(crate) struct MinorAxis<T: LineRSInt> {
+  pub(crate) sign: Sign,
+  pub(crate) magnitude: T::Magnitude,
+  bresenham_diff: SignedInt<T::Magnitude>,
+}
+
+impl<T: LineRSInt> MinorAxis<T> {
+  pub(crate) fn new(diff: SignedInt<T::Magnitude>, major_magnitude: T::Magnitude) -> MinorAxis<T> {
+    let tie_offset = major_magnitude - major_magnitude / T::Magnitude::line_rs_two();
+    MinorAxis {
+      sign: diff.sign,
+      magnitude: diff.magnitude,
+      bresenham_diff: SignedInt::diff_of(diff.magnitude, tie_offset),
+    }
+  }
+
+  // advances the error term by one major step, returning whether this
+  // axis moves too
+  pub(crate) fn step(&mut self, major_magnitude: T::Magnitude) -> bool {
+    if let Sign::Neg = self.bresenham_diff.sign {
+      self.bresenham_diff = self.bresenham_diff.add(self.magnitude);
+      false
+    } else {
+      self.bresenham_diff = self.bresenham_diff.sub(major_magnitude).add(self.magnitude);
+      true
+    }
+  }
+}
+
+/// Lazily yields the voxels of the line from `p1` to `p2`, both inclusive.
+///
+/// This is `BresenhamIter` with a second minor axis: the axis that moves
+/// furthest (the first of them, on a tie) steps every time, and each of
+/// the other two keeps its own error term. Viewed along either minor axis
+/// the line is exactly the 2D `calculate_line` between the projected
+/// endpoints.
+///
+/// As with `BresenhamIter`, a line of more than `usize::MAX` voxels has a
+/// `size_hint` of `(usize::MAX, None)`, and `len` panics on it.
+#[derive(Debug, Clone)]
+pub struct Bresenham3Iter<T: LineRSInt> {
+  position: [T; 3],
+  major: usize,
+  major_sign: Sign,
+  major_magnitude: T::Magnitude,
+  // the other two axes by index, and their error terms
+  minor_axes: [usize; 2],
+  minors: [MinorAxis<T>; 2],
+  left: T::Magnitude,
+  done: bool,
+}
+
+impl<T: LineRSInt> Bresenham3Iter<T> {
+  pub fn new(p1: Point3<T>, p2: Point3<T>) -> Bresenham3Iter<T> {
+    let diffs = [
+      SignedInt::diff_of(p2.x, p1.x),
+      SignedInt::diff_of(p2.y, p1.y),
+      SignedInt::diff_of(p2.z, p1.z),
+    ];
+    let mut major = 0;
+    for axis in 1..3 {
+      if diffs[axis].magnitude > diffs[major].magnitude {
+        major = axis;
+      }
+    }
+    let minor_axes = [(major + 1) % 3, (major + 2) % 3];
+    let major_magnitude = diffs[major].magnitude;
+    Bresenham3Iter {
+      position: [p1.x, p1.y, p1.z],
+      major,
+      major_sign: diffs[major].sign,
+      major_magnitude,
+      minor_axes,
+      minors: [
+        MinorAxis::new(diffs[minor_axes[0]], major_magnitude),
+        MinorAxis::new(diffs[minor_axes[1]], major_magnitude),
+      ],
+      left: major_magnitude,
+      done: false,
+    }
+  }
+}
+
+impl<T: LineRSInt> Iterator for Bresenham3Iter<T> {
+  type Item = Point3<T>;
+
+  fn next(&mut self) -> Option<Point3<T>> {
+    if self.done {
+      return None;
+    }
+    let [x, y, z] = self.position;
+    let one = T::Magnitude::line_rs_one();
+    if self.left == T::Magnitude::line_rs_zero() {
+      self.done = true;
+    } else {
+      self.left = self.left - one;
+      self.position[self.major] = offset(self.position[self.major], self.major_sign, one);
+      for (minor, &axis) in self.minors.iter_mut().zip(self.minor_axes.iter()) {
+        if minor.step(self.major_magnitude) {
+          self.position[axis] = offset(self.position[axis], minor.sign, one);
+        }
+      }
+    }
+    Some(Point3 { x, y, z })
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    if self.done {
+      return (0, Some(0));
+    }
+    match self.left.line_rs_to_usize().checked_add(1) {
+      Some(len) => (len, Some(len)),
+      None => (usize::MAX, None),
+    }
+  }
+}
+
+impl<T: LineRSInt> ExactSizeIterator for Bresenham3Iter<T> {}
+
+impl<T: LineRSInt> FusedIterator for Bresenham3Iter<T> {}
+
+/// All the voxels of `Bresenham3Iter::new(p1, p2)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_line_3d<T: LineRSInt>(
+  p1: Point3<T>,
+  p2: Point3<T>,
+) -> Vec<Point3<T>> {
+  Bresenham3Iter::new(p1, p2).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::calculate_line_3d;
+  use super::Bresenham3Iter;
+  use crate::bresenham::{calculate_line, Point, Point3};
+
+  #[test]
+  fn along_the_major_axis() {
+    let line = calculate_line_3d(Point3::new(0, 0, 0), Point3::new(4, 2, -1));
+    let expected = vec![
+      Point3::new(0, 0, 0),
+      Point3::new(1, 1, 0),
+      Point3::new(2, 1, -1),
+      Point3::new(3, 2, -1),
+      Point3::new(4, 2, -1),
+    ];
+    assert_eq!(line, expected);
+  }
+
+  #[test]
+  fn projections_match_the_2d_line() {
+    let ends = [(0i32, 0i32, 0i32), (9, 4, -3), (-2, 7, 5), (3, -3, 8), (-6, -1, -6), (5, 5, 5)];
+    for &(x1, y1, z1) in &ends {
+      for &(x2, y2, z2) in &ends {
+        let line = calculate_line_3d(Point3::new(x1, y1, z1), Point3::new(x2, y2, z2));
+        let a = [x1, y1, z1];
+        let b = [x2, y2, z2];
+        let coords = |p: &Point3<i32>| [p.x, p.y, p.z];
+        let major = (0..3).fold(0, |m, i| if (b[i] - a[i]).abs() > (b[m] - a[m]).abs() { i } else { m });
+        assert_eq!(line.len() as i32, (b[major] - a[major]).abs() + 1);
+        for minor in (0..3).filter(|&i| i != major) {
+          let flat = calculate_line(Point::new(a[major], a[minor]), Point::new(b[major], b[minor]));
+          let projected: Vec<_> = line.iter()
+            .map(|p| Point::new(coords(p)[major], coords(p)[minor]))
+            .collect();
+          assert_eq!(projected, flat);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn full_range_without_overflow() {
+    let mut iter = Bresenham3Iter::new(Point3::new(0u8, 255, 0), Point3::new(255, 0, 128));
+    assert_eq!(iter.len(), 256);
+    assert_eq!(iter.next(), Some(Point3::new(0, 255, 0)));
+    assert_eq!(iter.last(), Some(Point3::new(255, 0, 128)));
+    let iter = Bresenham3Iter::new(Point3::new(0u128, 0, 0), Point3::new(u128::MAX, 1, 2));
+    assert_eq!(iter.size_hint(), (usize::MAX, None));
+  }
+
+  #[test]
+  #[should_panic]
+  fn len_panics_past_usize_max() {
+    Bresenham3Iter::new(Point3::new(0u128, 0, 0), Point3::new(u128::MAX, 1, 2)).len();
+  }
+}
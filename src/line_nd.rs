@@ -0,0 +1,150 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{offset, LineRSInt, LineRSUint, PointN, SignedInt};
+use crate::line3d::MinorAxis;
+
+/// Lazily yields the points of the line from `p1` to `p2`, both inclusive,
+/// through a grid of `N` dimensions.
+///
+/// The axis that moves furthest (the first of them, on a tie) steps every
+/// time and every other axis keeps its own error term, so for `N` of 2 or
+/// 3 the points are those of `calculate_line` and `calculate_line_3d`.
+///
+/// As with `BresenhamIter`, a line of more than `usize::MAX` points has a
+/// `size_hint` of `(usize::MAX, None)`, and `len` panics on it.
+#[derive(Debug, Clone)]
+pub struct BresenhamNIter<T: LineRSInt, const N: usize> {
+  position: [T; N],
+  major_magnitude: T::Magnitude,
+  // the major axis is kept here too: its error term never goes negative,
+  // so it steps every time like the others step on their turns
+  axes: [MinorAxis<T>; N],
+  left: T::Magnitude,
+  done: bool,
+}
+
+impl<T: LineRSInt, const N: usize> BresenhamNIter<T, N> {
+  pub fn new(p1: PointN<T, N>, p2: PointN<T, N>) -> BresenhamNIter<T, N> {
+    let diffs: [SignedInt<T::Magnitude>; N] =
+      core::array::from_fn(|axis| SignedInt::diff_of(p2.coords[axis], p1.coords[axis]));
+    let mut major_magnitude = T::Magnitude::line_rs_zero();
+    for diff in diffs.iter() {
+      if diff.magnitude > major_magnitude {
+        major_magnitude = diff.magnitude;
+      }
+    }
+    BresenhamNIter {
+      position: p1.coords,
+      major_magnitude,
+      axes: core::array::from_fn(|axis| MinorAxis::new(diffs[axis], major_magnitude)),
+      left: major_magnitude,
+      done: false,
+    }
+  }
+}
+
+impl<T: LineRSInt, const N: usize> Iterator for BresenhamNIter<T, N> {
+  type Item = PointN<T, N>;
+
+  fn next(&mut self) -> Option<PointN<T, N>> {
+    if self.done {
+      return None;
+    }
+    let point = PointN { coords: self.position };
+    let one = T::Magnitude::line_rs_one();
+    if self.left == T::Magnitude::line_rs_zero() {
+      self.done = true;
+    } else {
+      self.left = self.left - one;
+      for (coord, axis) in self.position.iter_mut().zip(self.axes.iter_mut()) {
+        if axis.step(self.major_magnitude) {
+          *coord = offset(*coord, axis.sign, one);
+        }
+      }
+    }
+    Some(point)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    if self.done {
+      return (0, Some(0));
+    }
+    match self.left.line_rs_to_usize().checked_add(1) {
+      Some(len) => (len, Some(len)),
+      None => (usize::MAX, None),
+    }
+  }
+}
+
+impl<T: LineRSInt, const N: usize> ExactSizeIterator for BresenhamNIter<T, N> {}
+
+impl<T: LineRSInt, const N: usize> FusedIterator for BresenhamNIter<T, N> {}
+
+/// All the points of `BresenhamNIter::new(p1, p2)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_line_nd<T: LineRSInt, const N: usize>(
+  p1: PointN<T, N>,
+  p2: PointN<T, N>,
+) -> Vec<PointN<T, N>> {
+  BresenhamNIter::new(p1, p2).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::calculate_line_nd;
+  use super::BresenhamNIter;
+  use crate::bresenham::{calculate_line, Point, Point3, PointN};
+  use crate::line3d::calculate_line_3d;
+
+  #[test]
+  fn matches_the_2d_and_3d_lines() {
+    let ends = [(0i32, 0i32, 0i32), (9, 4, -3), (-2, 7, 5), (3, -3, 8), (-6, -1, -6), (5, 5, 5)];
+    for &(x1, y1, z1) in &ends {
+      for &(x2, y2, z2) in &ends {
+        let flat = calculate_line_nd(PointN::new([x1, y1]), PointN::new([x2, y2]));
+        let expected = calculate_line(Point::new(x1, y1), Point::new(x2, y2));
+        assert_eq!(flat.iter().map(|p| Point::new(p.coords[0], p.coords[1])).collect::<Vec<_>>(), expected);
+
+        // a fourth axis that doesn't move changes nothing
+        let line = calculate_line_nd(PointN::new([x1, y1, z1, 7]), PointN::new([x2, y2, z2, 7]));
+        let expected = calculate_line_3d(Point3::new(x1, y1, z1), Point3::new(x2, y2, z2));
+        let projected: Vec<_> = line.iter()
+          .map(|p| Point3::new(p.coords[0], p.coords[1], p.coords[2]))
+          .collect();
+        assert_eq!(projected, expected);
+        assert!(line.iter().all(|p| p.coords[3] == 7));
+      }
+    }
+  }
+
+  #[test]
+  fn four_dimensions() {
+    let line = calculate_line_nd(PointN::new([0, 0, 0, 0]), PointN::new([1, -4, 2, 3]));
+    let expected = vec![
+      PointN::new([0, 0, 0, 0]),
+      PointN::new([0, -1, 1, 1]),
+      PointN::new([1, -2, 1, 2]),
+      PointN::new([1, -3, 2, 2]),
+      PointN::new([1, -4, 2, 3]),
+    ];
+    assert_eq!(line, expected);
+  }
+
+  #[test]
+  fn full_range_without_overflow() {
+    let mut iter = BresenhamNIter::new(PointN::new([0u8, 255, 0, 9, 200]), PointN::new([255, 0, 128, 9, 1]));
+    assert_eq!(iter.len(), 256);
+    assert_eq!(iter.next(), Some(PointN::new([0, 255, 0, 9, 200])));
+    assert_eq!(iter.last(), Some(PointN::new([255, 0, 128, 9, 1])));
+    let iter = BresenhamNIter::new(PointN::new([0u128, 0, 0, 0]), PointN::new([u128::MAX, 1, 2, 3]));
+    assert_eq!(iter.size_hint(), (usize::MAX, None));
+  }
+
+  #[test]
+  #[should_panic]
+  fn len_panics_past_usize_max() {
+    BresenhamNIter::new(PointN::new([0u128, 0, 0, 0]), PointN::new([u128::MAX, 1, 2, 3])).len();
+  }
+}
@@ -0,0 +1,323 @@
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{floor_div, LineRSInt, LineRSWide, Point, Span};
+
+/// Which parts of a self-overlapping polygon count as inside.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FillRule {
+  /// Inside where a ray to infinity crosses the outline an odd number of
+  /// times, so overlapping parts cancel out.
+  EvenOdd,
+  /// Inside wherever the outline winds around the point at all, so
+  /// overlapping parts stay filled and only oppositely wound rings cut
+  /// holes.
+  NonZero,
+}
+
+// a non-horizontal edge, stepped one row at a time from its upper end with
+// the same error term arithmetic as a bresenham line
+#[derive(Debug, Clone)]
+struct Edge<T: LineRSInt> {
+  // the row after the last one the edge crosses
+  y_end: T::Wide,
+  // +1 for edges going down, -1 for edges going up
+  winding: i32,
+  // the first pixel column at or right of the edge on the current row,
+  // with e = x * dy - (the exact crossing times dy), in [0, dy)
+  x: T::Wide,
+  e: T::Wide,
+  dy: T::Wide,
+  // dx = x_step * dy + e_step, with e_step in [0, dy)
+  x_step: T::Wide,
+  e_step: T::Wide,
+}
+
+impl<T: LineRSInt> Edge<T> {
+  // None for horizontal edges. the ends and their differences must fit in
+  // Wide
+  fn new(a: Point<T>, b: Point<T>) -> Option<(T::Wide, Edge<T>)> {
+    let widen = |p: Point<T>| p.x.line_rs_widen().zip(p.y.line_rs_widen());
+    let (mut a, mut b) = (widen(a)?, widen(b)?);
+    let winding = if a.1 < b.1 {
+      1
+    } else if a.1 > b.1 {
+      core::mem::swap(&mut a, &mut b);
+      -1
+    } else {
+      return None;
+    };
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let x_step = floor_div(dx, dy);
+    let edge = Edge {
+      y_end: b.1,
+      winding,
+      x: a.0,
+      e: T::Wide::from(0),
+      dy,
+      x_step,
+      e_step: dx - x_step * dy,
+    };
+    Some((a.1, edge))
+  }
+
+  fn step(&mut self) {
+    self.x = self.x + self.x_step;
+    self.e = self.e - self.e_step;
+    if self.e < T::Wide::from(0) {
+      self.x = self.x + T::Wide::from(1);
+      self.e = self.e + self.dy;
+    }
+  }
+}
+
+/// Lazily yields the pixels inside a polygon made of one or more closed
+/// `rings`, as horizontal spans sorted from the top row down and left to
+/// right along each row.
+///
+/// Each ring is joined back from its last point to its first. Rings may
+/// cross themselves and each other, and `rule` decides which of the
+/// overlapping parts are filled. Touching runs of inside pixels come out as
+/// a single span.
+///
+/// A pixel is inside when its centre is, with the same top-left rule as
+/// `TriangleSpanIter` for centres exactly on an edge: with y growing
+/// downwards, they are inside along the top and left of a filled region
+/// and outside along its bottom and right. A triangle therefore fills just
+/// the pixels `TriangleSpanIter` does, and polygons sharing an edge never
+/// overlap or leave gaps between them.
+///
+/// Each edge is stepped from row to row with an error term rather than
+/// divided out again on every row, so no product of coordinates is ever
+/// formed: unlike `TriangleSpanIter`, it only needs the distance along each
+/// axis between the ends of every edge to fit in `LineRSInt::Wide`. A
+/// polygon with a longer edge fills nothing.
+#[derive(Debug, Clone)]
+pub struct PolygonSpanIter<T: LineRSInt> {
+  rule: FillRule,
+  // edges yet to be reached, sorted so the topmost is last
+  pending: Vec<(T::Wide, Edge<T>)>,
+  active: Vec<Edge<T>>,
+  y: T::Wide,
+  // the spans of the current row, reversed
+  row: Vec<Span<T>>,
+  // the crossings of the current row, reused between rows
+  crossings: Vec<(T::Wide, i32)>,
+}
+
+impl<T: LineRSInt> PolygonSpanIter<T> {
+  pub fn new<R: AsRef<[Point<T>]>>(rings: &[R], rule: FillRule) -> PolygonSpanIter<T> {
+    let pairs = || {
+      rings.iter().flat_map(|ring| {
+        let ring = ring.as_ref();
+        let closing = ring.last().map(|&last| (last, ring[0]));
+        ring.windows(2).map(|pair| (pair[0], pair[1])).chain(closing)
+      })
+    };
+    // a vertex out of range of Wide, or an edge too long for it, rejects
+    // the whole polygon, as leaving out its edges would fill the wrong
+    // pixels
+    let along = |a: T, b: T| b.line_rs_widen()?.line_rs_checked_sub(a.line_rs_widen()?);
+    let fits = pairs().all(|(a, b)| along(a.x, b.x).is_some() && along(a.y, b.y).is_some());
+    let mut pending = Vec::new();
+    if fits {
+      pending.extend(pairs().filter_map(|(a, b)| Edge::new(a, b)));
+    }
+    pending.sort_by_key(|edge: &(T::Wide, Edge<T>)| core::cmp::Reverse(edge.0));
+    let y = pending.last().map_or(T::Wide::from(0), |edge| edge.0);
+    PolygonSpanIter {
+      rule,
+      pending,
+      active: Vec::new(),
+      y,
+      row: Vec::new(),
+      crossings: Vec::new(),
+    }
+  }
+
+  fn inside(&self, winding: i32) -> bool {
+    match self.rule {
+      FillRule::EvenOdd => winding % 2 != 0,
+      FillRule::NonZero => winding != 0,
+    }
+  }
+
+  // fills self.row with the spans of row y
+  fn scan_row(&mut self, y: T::Wide) {
+    self.crossings.clear();
+    self.crossings.extend(self.active.iter().map(|edge| (edge.x, edge.winding)));
+    self.crossings.sort();
+    let mut winding = 0;
+    let mut start = T::Wide::from(0);
+    let mut i = 0;
+    while i < self.crossings.len() {
+      // crossings landing on the same column all take effect there at once
+      let x = self.crossings[i].0;
+      let was_inside = self.inside(winding);
+      while i < self.crossings.len() && self.crossings[i].0 == x {
+        winding += self.crossings[i].1;
+        i += 1;
+      }
+      match (was_inside, self.inside(winding)) {
+        (false, true) => start = x,
+        (true, false) => {
+          // every pixel inside lies between the vertices, so is in range
+          let span = (T::line_rs_narrow(y), T::line_rs_narrow(start), T::line_rs_narrow(x - T::Wide::from(1)));
+          if let (Some(y), Some(x_start), Some(x_end)) = span {
+            self.row.push(Span { y, x_start, x_end });
+          }
+        },
+        _ => {},
+      }
+    }
+    self.row.reverse();
+  }
+}
+
+impl<T: LineRSInt> Iterator for PolygonSpanIter<T> {
+  type Item = Span<T>;
+
+  fn next(&mut self) -> Option<Span<T>> {
+    loop {
+      if let Some(span) = self.row.pop() {
+        return Some(span);
+      }
+      if self.active.is_empty() {
+        // skip ahead over any gap between rings
+        match self.pending.last() {
+          Some(&(top, _)) => self.y = top,
+          None => return None,
+        }
+      }
+      let y = self.y;
+      while self.pending.last().is_some_and(|edge| edge.0 == y) {
+        if let Some((_, edge)) = self.pending.pop() {
+          self.active.push(edge);
+        }
+      }
+      self.scan_row(y);
+      self.y = y + T::Wide::from(1);
+      let next_y = self.y;
+      self.active.retain(|edge| edge.y_end > next_y);
+      self.active.iter_mut().for_each(Edge::step);
+    }
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for PolygonSpanIter<T> {}
+
+/// All the spans of `PolygonSpanIter::new(rings, rule)`.
+pub fn calculate_filled_polygon<T: LineRSInt, R: AsRef<[Point<T>]>>(rings: &[R], rule: FillRule) -> Vec<Span<T>> {
+  PolygonSpanIter::new(rings, rule).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{calculate_filled_polygon, FillRule};
+  use crate::bresenham::{Point, Span};
+  use crate::triangle::calculate_filled_triangle;
+
+  fn ring(points: &[(i32, i32)]) -> Vec<Point<i32>> {
+    points.iter().map(|&(x, y)| Point::new(x, y)).collect()
+  }
+
+  fn contains(spans: &[Span<i32>], x: i32, y: i32) -> bool {
+    spans.iter().any(|s| s.y == y && s.x_start <= x && x <= s.x_end)
+  }
+
+  #[test]
+  fn triangles_match_the_triangle_fill() {
+    let points = [(0, 0), (9, 2), (-4, 7), (5, -6), (3, 3), (12, 11), (-8, -1)];
+    for &a in &points {
+      for &b in &points {
+        for &c in &points {
+          let triangle = ring(&[a, b, c]);
+          let expected = calculate_filled_triangle(triangle[0], triangle[1], triangle[2]);
+          for &rule in &[FillRule::EvenOdd, FillRule::NonZero] {
+            assert_eq!(calculate_filled_polygon(&[&triangle], rule), expected, "{:?}", triangle);
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn fill_rules() {
+    // a pentagram overlaps itself in the middle
+    let star = ring(&[(0, -10), (6, 8), (-9, -3), (9, -3), (-6, 8)]);
+    let even_odd = calculate_filled_polygon(&[&star], FillRule::EvenOdd);
+    let non_zero = calculate_filled_polygon(&[&star], FillRule::NonZero);
+    assert!(!contains(&even_odd, 0, 0));
+    assert!(contains(&non_zero, 0, 0));
+    for spans in &[&even_odd, &non_zero] {
+      assert!(contains(spans, 0, -8));
+      assert!(contains(spans, 7, -3));
+      assert!(!contains(spans, 0, 7));
+    }
+
+    // a square with a hole: wound the other way, the inner ring cuts a hole
+    // under both rules, and wound the same way only under even-odd
+    let outer = ring(&[(0, 0), (10, 0), (10, 10), (0, 10)]);
+    let inner = ring(&[(3, 3), (3, 7), (7, 7), (7, 3)]);
+    let same_way: Vec<_> = inner.iter().rev().copied().collect();
+    let hole: Vec<_> = (0..10)
+      .flat_map(|y| match y {
+        3..=6 => vec![Span::new(y, 0, 2), Span::new(y, 7, 9)],
+        _ => vec![Span::new(y, 0, 9)],
+      })
+      .collect();
+    assert_eq!(calculate_filled_polygon(&[&outer, &inner], FillRule::EvenOdd), hole);
+    assert_eq!(calculate_filled_polygon(&[&outer, &inner], FillRule::NonZero), hole);
+    assert_eq!(calculate_filled_polygon(&[&outer, &same_way], FillRule::EvenOdd), hole);
+    let full: Vec<_> = (0..10).map(|y| Span::new(y, 0, 9)).collect();
+    assert_eq!(calculate_filled_polygon(&[&outer, &same_way], FillRule::NonZero), full);
+
+    // rings that touch along an edge merge into one span per row
+    let right = ring(&[(10, 0), (20, 0), (20, 10), (10, 10)]);
+    let merged: Vec<_> = (0..10).map(|y| Span::new(y, 0, 19)).collect();
+    assert_eq!(calculate_filled_polygon(&[&outer, &right], FillRule::EvenOdd), merged);
+  }
+
+  #[test]
+  fn spans_are_sorted_and_disjoint() {
+    let zigzag = ring(&[(0, 0), (4, 12), (8, 1), (12, 12), (16, 0), (20, 15), (-3, 14)]);
+    let far = ring(&[(30, 40), (35, 50), (25, 48)]);
+    for &rule in &[FillRule::EvenOdd, FillRule::NonZero] {
+      let spans = calculate_filled_polygon(&[&zigzag, &far], rule);
+      for pair in spans.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        assert!(a.y < b.y || (a.y == b.y && a.x_end + 1 < b.x_start), "{:?}", pair);
+      }
+      assert!(spans.iter().all(|s| s.x_start <= s.x_end));
+      assert!(contains(&spans, 30, 45));
+      assert!(contains(&spans, 8, 5));
+      assert!(!contains(&spans, 8, 0));
+    }
+    let empty: [Vec<Point<u8>>; 0] = [];
+    assert_eq!(calculate_filled_polygon(&empty, FillRule::NonZero), vec![]);
+  }
+
+  #[test]
+  fn limits_of_wide() {
+    use super::PolygonSpanIter;
+    // edges spanning all of i64 fit in i128, and ones spanning all of
+    // i128 don't
+    let (min, max) = (i64::MIN, i64::MAX);
+    let square = [Point::new(min, min), Point::new(max, min), Point::new(max, max), Point::new(min, max)];
+    let mut spans = PolygonSpanIter::new(&[&square[..]], FillRule::NonZero);
+    assert_eq!(spans.next(), Some(Span::new(min, min, max - 1)));
+    assert_eq!(spans.next(), Some(Span::new(min + 1, min, max - 1)));
+    let (min, max) = (i128::MIN, i128::MAX);
+    let square = [Point::new(min, min), Point::new(max, min), Point::new(max, max), Point::new(min, max)];
+    assert_eq!(PolygonSpanIter::new(&[&square[..]], FillRule::NonZero).next(), None);
+    let corner = [Point::new(max - 4, max - 4), Point::new(max, max - 4), Point::new(max, max)];
+    let spans = calculate_filled_polygon(&[&corner[..]], FillRule::NonZero);
+    assert_eq!(spans[0], Span::new(max - 4, max - 4, max - 1));
+    assert_eq!(spans.len(), 4);
+    // and a vertex past i128::MAX rejects the whole polygon
+    let near = [Point::new(0u128, 0), Point::new(4, 0), Point::new(0, 4)];
+    let far = [Point::new(u128::MAX - 4, 0), Point::new(u128::MAX, 0), Point::new(u128::MAX, 4)];
+    assert_eq!(calculate_filled_polygon(&[&near[..], &far[..]], FillRule::EvenOdd), vec![]);
+    assert_eq!(calculate_filled_polygon(&[&near[..]], FillRule::EvenOdd).len(), 4);
+  }
+}
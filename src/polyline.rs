@@ -0,0 +1,175 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{BresenhamIter, LineRSInt, Point};
+
+/// Whether a polyline ends at its last vertex or carries on back to its
+/// first.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PolylineKind {
+  /// The path stops at the last vertex.
+  Open,
+  /// A final segment joins the last vertex back to the first.
+  Closed,
+}
+
+/// Lazily yields the pixels of the lines joining consecutive `vertices`, as
+/// one connected path.
+///
+/// Each segment is drawn as `calculate_line` draws it, but the pixel where
+/// two segments meet is only yielded once, as is the first vertex of a
+/// closed polyline. Repeated vertices add nothing, and a single vertex is a
+/// single pixel. Pixels where the path crosses itself away from a vertex
+/// are still yielded once per crossing.
+#[derive(Debug, Clone)]
+pub struct PolylineIter<T: LineRSInt, I: Iterator<Item = Point<T>>> {
+  vertices: I,
+  kind: PolylineKind,
+  first: Option<Point<T>>,
+  // the vertex the current segment ends on
+  last: Option<Point<T>>,
+  segment: Option<BresenhamIter<T>>,
+  done: bool,
+}
+
+impl<T: LineRSInt, I: Iterator<Item = Point<T>>> PolylineIter<T, I> {
+  pub fn new<V: IntoIterator<Item = Point<T>, IntoIter = I>>(vertices: V, kind: PolylineKind) -> PolylineIter<T, I> {
+    PolylineIter {
+      vertices: vertices.into_iter(),
+      kind,
+      first: None,
+      last: None,
+      segment: None,
+      done: false,
+    }
+  }
+}
+
+impl<T: LineRSInt, I: Iterator<Item = Point<T>>> Iterator for PolylineIter<T, I> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    loop {
+      if let Some(point) = self.segment.as_mut().and_then(Iterator::next) {
+        return Some(point);
+      }
+      if self.done {
+        return None;
+      }
+      let last = match self.last {
+        Some(last) => last,
+        None => match self.vertices.next() {
+          Some(first) => {
+            self.first = Some(first);
+            self.last = Some(first);
+            return Some(first);
+          },
+          None => {
+            self.done = true;
+            return None;
+          },
+        },
+      };
+      // each segment leaves out the vertex it starts on, which the one
+      // before already yielded
+      let mut segment = match self.vertices.next() {
+        Some(vertex) => {
+          self.last = Some(vertex);
+          BresenhamIter::new(last, vertex)
+        },
+        None => {
+          self.done = true;
+          match (self.kind, self.first) {
+            (PolylineKind::Closed, Some(first)) => {
+              // nor does the closing one yield the first vertex again
+              let mut segment = BresenhamIter::new(last, first);
+              segment.next_back();
+              segment
+            },
+            _ => return None,
+          }
+        },
+      };
+      segment.next();
+      self.segment = Some(segment);
+    }
+  }
+}
+
+impl<T: LineRSInt, I: Iterator<Item = Point<T>>> FusedIterator for PolylineIter<T, I> {}
+
+/// All the pixels of `PolylineIter::new(vertices, kind)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_polyline<T: LineRSInt>(vertices: &[Point<T>], kind: PolylineKind) -> Vec<Point<T>> {
+  PolylineIter::new(vertices.iter().copied(), kind).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::{calculate_polyline, PolylineIter, PolylineKind};
+  use crate::bresenham::{calculate_line, Point};
+  use crate::test_util::sorted_distinct;
+
+  #[test]
+  fn vertices_are_yielded_once() {
+    let vertices = [Point::new(0, 0), Point::new(3, 0), Point::new(3, 2), Point::new(0, 2)];
+    let open = calculate_polyline(&vertices, PolylineKind::Open);
+    let expected = vec![
+      Point::new(0, 0),
+      Point::new(1, 0),
+      Point::new(2, 0),
+      Point::new(3, 0),
+      Point::new(3, 1),
+      Point::new(3, 2),
+      Point::new(2, 2),
+      Point::new(1, 2),
+      Point::new(0, 2),
+    ];
+    assert_eq!(open, expected);
+
+    // closing the rectangle adds its left side, without its ends
+    let closed = calculate_polyline(&vertices, PolylineKind::Closed);
+    assert_eq!(closed[..open.len()], open[..]);
+    assert_eq!(closed[open.len()..], [Point::new(0, 1)]);
+    sorted_distinct(&closed);
+  }
+
+  #[test]
+  fn segments_match_calculate_line() {
+    let vertices = [Point::new(-4, 7), Point::new(9, 2), Point::new(9, 2), Point::new(3, -6), Point::new(-5, -1)];
+    for &kind in &[PolylineKind::Open, PolylineKind::Closed] {
+      let mut expected = vec![vertices[0]];
+      let mut ends = vertices.to_vec();
+      if kind == PolylineKind::Closed {
+        ends.push(vertices[0]);
+      }
+      for pair in ends.windows(2) {
+        expected.extend(calculate_line(pair[0], pair[1]).into_iter().skip(1));
+      }
+      if kind == PolylineKind::Closed {
+        expected.pop();
+      }
+      // any iterator of points works, not just slices
+      let from_iter: Vec<_> = PolylineIter::new(vertices.iter().copied(), kind).collect();
+      assert_eq!(from_iter, expected);
+      assert_eq!(calculate_polyline(&vertices, kind), expected);
+    }
+  }
+
+  #[test]
+  fn degenerate_polylines() {
+    for &kind in &[PolylineKind::Open, PolylineKind::Closed] {
+      assert_eq!(calculate_polyline::<i32>(&[], kind), vec![]);
+      assert_eq!(calculate_polyline(&[Point::new(2, 3)], kind), vec![Point::new(2, 3)]);
+      assert_eq!(calculate_polyline(&[Point::new(2, 3), Point::new(2, 3)], kind), vec![Point::new(2, 3)]);
+    }
+    let there_and_back = [Point::new(0u8, 0), Point::new(2, 0)];
+    assert_eq!(calculate_polyline(&there_and_back, PolylineKind::Closed), vec![
+      Point::new(0, 0),
+      Point::new(1, 0),
+      Point::new(2, 0),
+      Point::new(1, 0),
+    ]);
+  }
+}
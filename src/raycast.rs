@@ -0,0 +1,202 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{LineRSInt, Point};
+
+/// The side of a cell a ray enters it through.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Face {
+  MinX,
+  MaxX,
+  MinY,
+  MaxY,
+}
+
+/// A cell crossed by a ray, with the ray parameter `t` at which it enters
+/// the cell and the face it enters through. The cell the ray starts in has
+/// `t` of 0 and no face.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RayCell<T> {
+  pub cell: Point<T>,
+  pub t: f64,
+  pub face: Option<Face>,
+}
+
+/// Lazily yields the grid cells crossed by the ray `origin + t * direction`
+/// for `0 <= t <= max_t`, in order, using the traversal of Amanatides and
+/// Woo.
+///
+/// As elsewhere in the crate, cells are unit squares centred on integer
+/// points, so the ray starts in the cell nearest `origin`. `t` is measured
+/// in lengths of `direction`, so with a unit direction `max_t` is a
+/// distance. Where the ray passes exactly through a corner it steps along x
+/// first.
+///
+/// The walk stops when it leaves the range of `T`, and yields nothing if
+/// `origin` is outside that range or not finite.
+#[derive(Debug, Clone)]
+pub struct RayCastIter<T> {
+  x: i64,
+  y: i64,
+  step_x: i64,
+  step_y: i64,
+  // t at which the next x (or y) boundary is crossed, and the t between
+  // successive x (or y) boundaries
+  t_max_x: f64,
+  t_max_y: f64,
+  t_delta_x: f64,
+  t_delta_y: f64,
+  max_t: f64,
+  // entry t and face of the current cell, or None once finished
+  entry: Option<(f64, Option<Face>)>,
+  _cell: core::marker::PhantomData<T>,
+}
+
+// the nearest cell centre to v, without needing std for f64::floor
+fn nearest_cell(v: f64) -> Option<i64> {
+  let v = v + 0.5;
+  // between i64::MIN and i64::MAX + 1 as floats, and so not NaN
+  if !(-9_223_372_036_854_775_808.0..9_223_372_036_854_775_808.0).contains(&v) {
+    return None;
+  }
+  let truncated = v as i64;
+  Some(if truncated as f64 > v { truncated - 1 } else { truncated })
+}
+
+// the step direction along one axis, the t of the first boundary crossing
+// and the t between crossings
+fn axis(cell: i64, origin: f64, direction: f64) -> (i64, f64, f64) {
+  if direction > 0.0 {
+    (1, (cell as f64 + 0.5 - origin) / direction, 1.0 / direction)
+  } else if direction < 0.0 {
+    (-1, (cell as f64 - 0.5 - origin) / direction, -1.0 / direction)
+  } else {
+    (0, f64::INFINITY, f64::INFINITY)
+  }
+}
+
+impl<T: LineRSInt + TryFrom<i64>> RayCastIter<T> {
+  pub fn new(origin: Point<f64>, direction: Point<f64>, max_t: f64) -> RayCastIter<T> {
+    let start = nearest_cell(origin.x).zip(nearest_cell(origin.y));
+    let (x, y) = start.unwrap_or((0, 0));
+    let (step_x, t_max_x, t_delta_x) = axis(x, origin.x, direction.x);
+    let (step_y, t_max_y, t_delta_y) = axis(y, origin.y, direction.y);
+    RayCastIter {
+      x,
+      y,
+      step_x,
+      step_y,
+      t_max_x,
+      t_max_y,
+      t_delta_x,
+      t_delta_y,
+      max_t,
+      entry: start.map(|_| (0.0, None)),
+      _cell: core::marker::PhantomData,
+    }
+  }
+}
+
+impl<T: LineRSInt + TryFrom<i64>> Iterator for RayCastIter<T> {
+  type Item = RayCell<T>;
+
+  fn next(&mut self) -> Option<RayCell<T>> {
+    let (t, face) = self.entry.take()?;
+    let cell = Point {
+      x: T::try_from(self.x).ok()?,
+      y: T::try_from(self.y).ok()?,
+    };
+
+    let next_t = self.t_max_x.min(self.t_max_y);
+    if next_t.is_finite() && next_t <= self.max_t {
+      let step = if self.t_max_x <= self.t_max_y {
+        self.t_max_x += self.t_delta_x;
+        self.x.checked_add(self.step_x).map(|x| {
+          self.x = x;
+          if self.step_x > 0 { Face::MinX } else { Face::MaxX }
+        })
+      } else {
+        self.t_max_y += self.t_delta_y;
+        self.y.checked_add(self.step_y).map(|y| {
+          self.y = y;
+          if self.step_y > 0 { Face::MinY } else { Face::MaxY }
+        })
+      };
+      self.entry = step.map(|face| (next_t, Some(face)));
+    }
+    Some(RayCell { cell, t, face })
+  }
+}
+
+impl<T: LineRSInt + TryFrom<i64>> FusedIterator for RayCastIter<T> {}
+
+/// All the cells of `RayCastIter::new(origin, direction, max_t)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_ray_cells<T: LineRSInt + TryFrom<i64>>(
+  origin: Point<f64>,
+  direction: Point<f64>,
+  max_t: f64,
+) -> Vec<RayCell<T>> {
+  RayCastIter::new(origin, direction, max_t).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::calculate_ray_cells;
+  use super::Face;
+  use super::RayCell;
+  use crate::bresenham::Point;
+
+  #[test]
+  fn horizontal_ray_stops_at_max_t() {
+    let cells = calculate_ray_cells::<i32>(Point::new(0.0, 0.2), Point::new(1.0, 0.0), 3.0);
+    let expected = vec![
+      RayCell { cell: Point::new(0, 0), t: 0.0, face: None },
+      RayCell { cell: Point::new(1, 0), t: 0.5, face: Some(Face::MinX) },
+      RayCell { cell: Point::new(2, 0), t: 1.5, face: Some(Face::MinX) },
+      RayCell { cell: Point::new(3, 0), t: 2.5, face: Some(Face::MinX) },
+    ];
+    assert_eq!(cells, expected);
+
+    // with no direction there is only the starting cell
+    let cells = calculate_ray_cells::<i32>(Point::new(-2.7, 4.4), Point::new(0.0, 0.0), 10.0);
+    assert_eq!(cells, vec![RayCell { cell: Point::new(-3, 4), t: 0.0, face: None }]);
+  }
+
+  #[test]
+  fn rays_enter_each_cell_through_its_face() {
+    let origins = [(0.2, 0.1), (-3.4, 2.45), (7.0, -1.0)];
+    let directions = [(3.0, 1.3), (-0.4, 2.0), (-1.0, -1.0), (0.0, -2.5), (1.0, 0.0)];
+    for &(ox, oy) in &origins {
+      for &(dx, dy) in &directions {
+        let cells = calculate_ray_cells::<i32>(Point::new(ox, oy), Point::new(dx, dy), 4.0);
+        assert!(cells.len() > 1);
+        for pair in cells.windows(2) {
+          let (from, to) = (pair[0], pair[1]);
+          assert!(from.t <= to.t && to.t <= 4.0);
+          let (x, y) = (ox + to.t * dx, oy + to.t * dy);
+          let (cx, cy) = (to.cell.x as f64, to.cell.y as f64);
+          let (moved, boundary) = match to.face.unwrap() {
+            Face::MinX => ((1, 0), (x - (cx - 0.5)).abs()),
+            Face::MaxX => ((-1, 0), (x - (cx + 0.5)).abs()),
+            Face::MinY => ((0, 1), (y - (cy - 0.5)).abs()),
+            Face::MaxY => ((0, -1), (y - (cy + 0.5)).abs()),
+          };
+          assert_eq!((to.cell.x - from.cell.x, to.cell.y - from.cell.y), moved);
+          assert!(boundary < 1e-9, "{:?} entered away from its face", to);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn stops_at_the_edge_of_the_range() {
+    let cells = calculate_ray_cells::<u8>(Point::new(2.0, 1.0), Point::new(-1.0, 0.0), f64::INFINITY);
+    let xs: Vec<_> = cells.iter().map(|c| c.cell.x).collect();
+    assert_eq!(xs, vec![2, 1, 0]);
+    assert!(calculate_ray_cells::<u8>(Point::new(-3.0, 1.0), Point::new(1.0, 0.0), 10.0).is_empty());
+    assert!(calculate_ray_cells::<i32>(Point::new(f64::NAN, 1.0), Point::new(1.0, 0.0), 10.0).is_empty());
+  }
+}
@@ -0,0 +1,220 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{offset, LineRSInt, Point, Sign, SignedInt};
+
+/// A straight run of consecutive pixels of a line, along its major axis.
+///
+/// The ends are given in the order the line visits them, so a line going
+/// left (or up) has runs whose start is past their end. Unlike a `Span`,
+/// a horizontal run is therefore not always in increasing order.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineRun<T> {
+  /// Pixels along row `y`, for lines at least as wide as they are tall.
+  Horizontal { y: T, x_start: T, x_end: T },
+  /// Pixels along column `x`, for lines taller than they are wide.
+  Vertical { x: T, y_start: T, y_end: T },
+}
+
+/// Lazily yields the pixels of `calculate_line(p1, p2)` as runs: the
+/// horizontal runs of a line at least as wide as it is tall, or else the
+/// vertical ones, in order along the line.
+///
+/// Laying the runs end to end gives back exactly the points of
+/// `calculate_line`. Rather than stepping pixel by pixel, this is run-slice
+/// Bresenham: the major length is divided by the minor length once up
+/// front, and from then on each run is that quotient long, or one longer,
+/// as an error term carrying the remainder decides. Long shallow lines
+/// cost one step per run instead of one per pixel.
+#[derive(Debug, Clone)]
+pub struct LineRunIter<T: LineRSInt> {
+  vertical: bool,
+  // the line's start, as (major, minor) coordinates
+  major: T,
+  minor: T,
+  major_sign: Sign,
+  minor_sign: Sign,
+  x_magnitude: T::Magnitude,
+  y_magnitude: T::Magnitude,
+  // x = run_q * y + run_r
+  run_q: T::Magnitude,
+  run_r: T::Magnitude,
+  // the current run is the m-th, starting `start` steps along the line.
+  // the next starts at next_start = ceil(n / y) for a running numerator n,
+  // with e = next_start * y - n in [0, y).
+  m: T::Magnitude,
+  start: T::Magnitude,
+  next_start: T::Magnitude,
+  e: T::Magnitude,
+  done: bool,
+}
+
+impl<T: LineRSInt> LineRunIter<T> {
+  pub fn new(p1: Point<T>, p2: Point<T>) -> LineRunIter<T> {
+    let zero = T::Magnitude::line_rs_zero();
+    let one = T::Magnitude::line_rs_one();
+    let mut x_diff = SignedInt::diff_of(p2.x, p1.x);
+    let mut y_diff = SignedInt::diff_of(p2.y, p1.y);
+    let vertical = x_diff.magnitude < y_diff.magnitude;
+    let (major, minor) = if vertical {
+      core::mem::swap(&mut x_diff, &mut y_diff);
+      (p1.y, p1.x)
+    } else {
+      (p1.x, p1.y)
+    };
+    let (x, y) = (x_diff.magnitude, y_diff.magnitude);
+    // the minor axis moves for the m-th time at step ceil(((m - 1) * x + t) / y),
+    // with t the tie offset of BresenhamIter::new. so the first move is at
+    // ceil(t / y), and each one after comes x / y steps later.
+    let tie_offset = x - x / T::Magnitude::line_rs_two();
+    let (run_q, run_r, next_start, e) = if y == zero {
+      (zero, zero, zero, zero)
+    } else {
+      let (q, r) = (tie_offset / y, tie_offset % y);
+      let (next_start, e) = if r == zero { (q, zero) } else { (q + one, y - r) };
+      (x / y, x % y, next_start, e)
+    };
+    LineRunIter {
+      vertical,
+      major,
+      minor,
+      major_sign: x_diff.sign,
+      minor_sign: y_diff.sign,
+      x_magnitude: x,
+      y_magnitude: y,
+      run_q,
+      run_r,
+      m: zero,
+      start: zero,
+      next_start,
+      e,
+      done: false,
+    }
+  }
+}
+
+impl<T: LineRSInt> Iterator for LineRunIter<T> {
+  type Item = LineRun<T>;
+
+  fn next(&mut self) -> Option<LineRun<T>> {
+    if self.done {
+      return None;
+    }
+    let one = T::Magnitude::line_rs_one();
+    let last = self.m == self.y_magnitude;
+    let end = if last { self.x_magnitude } else { self.next_start - one };
+    let minor = offset(self.minor, self.minor_sign, self.m);
+    let (start, end) = (offset(self.major, self.major_sign, self.start), offset(self.major, self.major_sign, end));
+
+    if last {
+      self.done = true;
+    } else {
+      self.m = self.m + one;
+      self.start = self.next_start;
+      if self.m < self.y_magnitude {
+        // n grows by x = run_q * y + run_r, so next_start grows by run_q,
+        // and by one more whenever the remainder carries
+        self.next_start = self.next_start + self.run_q;
+        if self.e < self.run_r {
+          self.next_start = self.next_start + one;
+          self.e = self.e + (self.y_magnitude - self.run_r);
+        } else {
+          self.e = self.e - self.run_r;
+        }
+      }
+    }
+
+    Some(if self.vertical {
+      LineRun::Vertical { x: minor, y_start: start, y_end: end }
+    } else {
+      LineRun::Horizontal { y: minor, x_start: start, x_end: end }
+    })
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for LineRunIter<T> {}
+
+/// All the runs of `LineRunIter::new(p1, p2)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_line_runs<T: LineRSInt>(p1: Point<T>, p2: Point<T>) -> Vec<LineRun<T>> {
+  LineRunIter::new(p1, p2).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::{calculate_line_runs, LineRun};
+  use crate::bresenham::{calculate_line, Point};
+
+  fn run_points(run: LineRun<i32>) -> Vec<Point<i32>> {
+    let (fixed, start, end, vertical) = match run {
+      LineRun::Horizontal { y, x_start, x_end } => (y, x_start, x_end, false),
+      LineRun::Vertical { x, y_start, y_end } => (x, y_start, y_end, true),
+    };
+    let step = if start <= end { 1 } else { -1 };
+    (0..=(end - start).abs())
+      .map(|i| if vertical { Point::new(fixed, start + i * step) } else { Point::new(start + i * step, fixed) })
+      .collect()
+  }
+
+  #[test]
+  fn shallow_and_steep_lines() {
+    let runs = calculate_line_runs(Point::new(0, 0), Point::new(7, 2));
+    let expected = vec![
+      LineRun::Horizontal { y: 0, x_start: 0, x_end: 1 },
+      LineRun::Horizontal { y: 1, x_start: 2, x_end: 5 },
+      LineRun::Horizontal { y: 2, x_start: 6, x_end: 7 },
+    ];
+    assert_eq!(runs, expected);
+    let runs = calculate_line_runs(Point::new(0, 0), Point::new(-2, -7));
+    let expected = vec![
+      LineRun::Vertical { x: 0, y_start: 0, y_end: -1 },
+      LineRun::Vertical { x: -1, y_start: -2, y_end: -5 },
+      LineRun::Vertical { x: -2, y_start: -6, y_end: -7 },
+    ];
+    assert_eq!(runs, expected);
+    let runs = calculate_line_runs(Point::new(5, 0), Point::new(0, 1));
+    let expected = vec![
+      LineRun::Horizontal { y: 0, x_start: 5, x_end: 3 },
+      LineRun::Horizontal { y: 1, x_start: 2, x_end: 0 },
+    ];
+    assert_eq!(runs, expected);
+    assert_eq!(calculate_line_runs(Point::new(3u8, 4), Point::new(3, 4)), vec![LineRun::Horizontal { y: 4, x_start: 3, x_end: 3 }]);
+  }
+
+  #[test]
+  fn runs_lay_out_the_line() {
+    let ends = [(0, 0), (13, 4), (-9, 11), (5, -17), (-6, -6), (20, 1), (0, 8), (-12, 0)];
+    for &(x1, y1) in &ends {
+      for &(x2, y2) in &ends {
+        let (p1, p2) = (Point::new(x1, y1), Point::new(x2, y2));
+        let runs = calculate_line_runs(p1, p2);
+        let points: Vec<_> = runs.iter().flat_map(|&run| run_points(run)).collect();
+        assert_eq!(points, calculate_line(p1, p2), "{:?} to {:?}", p1, p2);
+      }
+    }
+    for x in -20..=20 {
+      for y in -20..=20 {
+        let (p1, p2) = (Point::new(0, 0), Point::new(x, y));
+        let points: Vec<_> = calculate_line_runs(p1, p2).into_iter().flat_map(run_points).collect();
+        assert_eq!(points, calculate_line(p1, p2), "{:?} to {:?}", p1, p2);
+      }
+    }
+  }
+
+  #[test]
+  fn full_range_without_overflow() {
+    let runs = calculate_line_runs(Point::new(i8::MIN, i8::MIN), Point::new(i8::MAX, i8::MIN + 3));
+    let expected = vec![
+      LineRun::Horizontal { y: -128, x_start: -128, x_end: -86 },
+      LineRun::Horizontal { y: -127, x_start: -85, x_end: -1 },
+      LineRun::Horizontal { y: -126, x_start: 0, x_end: 84 },
+      LineRun::Horizontal { y: -125, x_start: 85, x_end: 127 },
+    ];
+    assert_eq!(runs, expected);
+    let runs = calculate_line_runs(Point::new(0u8, 255), Point::new(255, 0));
+    assert_eq!(runs.len(), 256);
+    assert_eq!(runs[0], LineRun::Horizontal { y: 255, x_start: 0, x_end: 0 });
+    assert_eq!(runs[255], LineRun::Horizontal { y: 0, x_start: 255, x_end: 255 });
+  }
+}
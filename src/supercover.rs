@@ -0,0 +1,308 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{offset, LineRSInt, Point, Sign, SignedInt};
+
+/// What to do where the segment passes exactly through the corner shared by
+/// four cells, touching the two cells beside the diagonal only at a point.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CornerPolicy {
+  /// Include both cells beside the corner, so every cell the closed
+  /// segment touches is visited.
+  Both,
+  /// Step straight across the corner, visiting only the cells the segment
+  /// passes through the inside of.
+  Diagonal,
+}
+
+// which cell boundary the segment crosses next
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum GridStep {
+  X,
+  Y,
+  Corner,
+}
+
+// walks the cells (unit squares centred on integer points) that the segment
+// between two cell centres passes through, one boundary crossing at a time.
+#[derive(Debug, Clone)]
+pub(crate) struct GridWalk<T: LineRSInt> {
+  x: T,
+  y: T,
+  x_sign: Sign,
+  y_sign: Sign,
+  x_magnitude: T::Magnitude,
+  y_magnitude: T::Magnitude,
+  // crossings still to make on each axis
+  x_left: T::Magnitude,
+  y_left: T::Magnitude,
+  // with (ix, iy) the steps taken so far, the next x boundary is crossed
+  // before the next y boundary when e = (2ix + 1) * ny - (2iy + 1) * nx is
+  // negative, and both at once when it is zero. e starts at ny - nx and
+  // moves by 2ny and -2nx, so as with the bresenham error term only the
+  // half h = (e - parity) / 2 is kept, parity being that of nx + ny.
+  parity: bool,
+  bresenham_diff: SignedInt<T::Magnitude>,
+}
+
+impl<T: LineRSInt> GridWalk<T> {
+  pub(crate) fn new(p1: Point<T>, p2: Point<T>) -> GridWalk<T> {
+    let x_diff = SignedInt::diff_of(p2.x, p1.x);
+    let y_diff = SignedInt::diff_of(p2.y, p1.y);
+    let (nx, ny) = (x_diff.magnitude, y_diff.magnitude);
+    let two = T::Magnitude::line_rs_two();
+    // parity of nx + ny, without the sum overflowing
+    let zero = T::Magnitude::line_rs_zero();
+    let parity = (nx % two == zero) != (ny % two == zero);
+    // h = floor((ny - nx) / 2)
+    let bresenham_diff = if ny >= nx {
+      SignedInt::diff_of((ny - nx) / two, zero)
+    } else {
+      SignedInt::diff_of(zero, (nx - ny) - (nx - ny) / two)
+    };
+    GridWalk {
+      x: p1.x,
+      y: p1.y,
+      x_sign: x_diff.sign,
+      y_sign: y_diff.sign,
+      x_magnitude: nx,
+      y_magnitude: ny,
+      x_left: nx,
+      y_left: ny,
+      parity,
+      bresenham_diff,
+    }
+  }
+
+  // crossings still to make along x and along y
+  pub(crate) fn steps_left(&self) -> (T::Magnitude, T::Magnitude) {
+    (self.x_left, self.y_left)
+  }
+
+  pub(crate) fn position(&self) -> Point<T> {
+    Point { x: self.x, y: self.y }
+  }
+
+  // the cell one step along x (or y) from the current one
+  pub(crate) fn beside(&self, step: GridStep) -> Point<T> {
+    let one = T::Magnitude::line_rs_one();
+    match step {
+      GridStep::X => Point { x: offset(self.x, self.x_sign, one), y: self.y },
+      GridStep::Y => Point { x: self.x, y: offset(self.y, self.y_sign, one) },
+      GridStep::Corner => Point {
+        x: offset(self.x, self.x_sign, one),
+        y: offset(self.y, self.y_sign, one),
+      },
+    }
+  }
+
+  // the next crossing, or None at the last cell
+  pub(crate) fn next_step(&self) -> Option<GridStep> {
+    let zero = T::Magnitude::line_rs_zero();
+    if self.x_left == zero && self.y_left == zero {
+      return None;
+    }
+    let h = self.bresenham_diff;
+    Some(match h.sign {
+      Sign::Neg => GridStep::X,
+      Sign::Pos if h.magnitude == zero && !self.parity => GridStep::Corner,
+      Sign::Pos => GridStep::Y,
+    })
+  }
+
+  pub(crate) fn take(&mut self, step: GridStep) {
+    let one = T::Magnitude::line_rs_one();
+    let next = self.beside(step);
+    self.x = next.x;
+    self.y = next.y;
+    // h <= ny whenever a y step is taken, so subtracting never overflows
+    self.bresenham_diff = match step {
+      GridStep::X => {
+        self.x_left = self.x_left - one;
+        self.bresenham_diff.add(self.y_magnitude)
+      },
+      GridStep::Y => {
+        self.y_left = self.y_left - one;
+        self.bresenham_diff.sub(self.x_magnitude)
+      },
+      GridStep::Corner => {
+        self.x_left = self.x_left - one;
+        self.y_left = self.y_left - one;
+        self.bresenham_diff.add(self.y_magnitude).sub(self.x_magnitude)
+      },
+    };
+  }
+}
+
+/// Lazily yields every grid cell the segment from the centre of `p1` to the
+/// centre of `p2` passes through, in order, so that no wall can be slipped
+/// past between two consecutive cells.
+///
+/// Away from corners consecutive cells share an edge. Where the segment
+/// crosses a corner exactly, `policy` decides whether the two cells beside
+/// it are included (after the cell before the corner, the one along x
+/// first) or the walk steps diagonally.
+#[derive(Debug, Clone)]
+pub struct SupercoverIter<T: LineRSInt> {
+  walk: GridWalk<T>,
+  policy: CornerPolicy,
+  beside_x: Option<Point<T>>,
+  beside_y: Option<Point<T>>,
+  done: bool,
+}
+
+impl<T: LineRSInt> SupercoverIter<T> {
+  pub fn new(p1: Point<T>, p2: Point<T>, policy: CornerPolicy) -> SupercoverIter<T> {
+    SupercoverIter {
+      walk: GridWalk::new(p1, p2),
+      policy,
+      beside_x: None,
+      beside_y: None,
+      done: false,
+    }
+  }
+}
+
+impl<T: LineRSInt> Iterator for SupercoverIter<T> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    if let Some(point) = self.beside_x.take() {
+      return Some(point);
+    }
+    if let Some(point) = self.beside_y.take() {
+      return Some(point);
+    }
+    if self.done {
+      return None;
+    }
+    let point = self.walk.position();
+    match self.walk.next_step() {
+      None => self.done = true,
+      Some(step) => {
+        if step == GridStep::Corner && self.policy == CornerPolicy::Both {
+          self.beside_x = Some(self.walk.beside(GridStep::X));
+          self.beside_y = Some(self.walk.beside(GridStep::Y));
+        }
+        self.walk.take(step);
+      },
+    }
+    Some(point)
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for SupercoverIter<T> {}
+
+/// All the cells of `SupercoverIter::new(p1, p2, policy)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_supercover_line<T: LineRSInt>(
+  p1: Point<T>,
+  p2: Point<T>,
+  policy: CornerPolicy,
+) -> Vec<Point<T>> {
+  SupercoverIter::new(p1, p2, policy).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::calculate_supercover_line;
+  use super::CornerPolicy;
+  use crate::bresenham::Point;
+  use crate::test_util::sorted;
+
+  // does the closed segment a-b touch the closed cell centred on c? each
+  // axis limits t (as a fraction over 2 * |d|) to an interval.
+  fn touches(a: Point<i64>, b: Point<i64>, c: Point<i64>) -> bool {
+    let mut lo = (0, 1);
+    let mut hi = (1, 1);
+    for &(from, to, centre) in &[(a.x, b.x, c.x), (a.y, b.y, c.y)] {
+      let d = to - from;
+      if d == 0 {
+        if (2 * (from - centre)).abs() > 1 {
+          return false;
+        }
+        continue;
+      }
+      let (mut t1, mut t2) = ((2 * (centre - from) - 1, 2 * d), (2 * (centre - from) + 1, 2 * d));
+      if d < 0 {
+        t1 = (-t1.0, -t1.1);
+        t2 = (-t2.0, -t2.1);
+        std::mem::swap(&mut t1, &mut t2);
+      }
+      // fractions with positive denominators compare by cross-multiplying
+      if t1.0 * lo.1 > lo.0 * t1.1 {
+        lo = t1;
+      }
+      if t2.0 * hi.1 < hi.0 * t2.1 {
+        hi = t2;
+      }
+    }
+    lo.0 * hi.1 <= hi.0 * lo.1
+  }
+
+  #[test]
+  fn corners() {
+    let both = calculate_supercover_line(Point::new(0, 0), Point::new(2, 2), CornerPolicy::Both);
+    let expected = vec![
+      Point::new(0, 0),
+      Point::new(1, 0),
+      Point::new(0, 1),
+      Point::new(1, 1),
+      Point::new(2, 1),
+      Point::new(1, 2),
+      Point::new(2, 2),
+    ];
+    assert_eq!(both, expected);
+    let diagonal = calculate_supercover_line(Point::new(0, 0), Point::new(2, 2), CornerPolicy::Diagonal);
+    assert_eq!(diagonal, vec![Point::new(0, 0), Point::new(1, 1), Point::new(2, 2)]);
+
+    // a single corner crossing halfway along
+    let line = calculate_supercover_line(Point::new(0u8, 0), Point::new(3, 1), CornerPolicy::Diagonal);
+    let expected = vec![Point::new(0, 0), Point::new(1, 0), Point::new(2, 1), Point::new(3, 1)];
+    assert_eq!(line, expected);
+  }
+
+  #[test]
+  fn visits_exactly_the_touched_cells() {
+    let ends = [(0, 0), (5, 2), (-3, 4), (-6, -6), (2, -7), (0, 5), (-4, 0), (1, 1)];
+    for &(x1, y1) in &ends {
+      for &(x2, y2) in &ends {
+        let a = Point::new(x1, y1);
+        let b = Point::new(x2, y2);
+        let line = calculate_supercover_line(a, b, CornerPolicy::Both);
+        let mut expected = vec![];
+        for x in -7..=7 {
+          for y in -8..=8 {
+            if touches(a, b, Point::new(x, y)) {
+              expected.push((x, y));
+            }
+          }
+        }
+        assert_eq!(sorted(&line), expected, "{:?} to {:?}", a, b);
+        assert_eq!(line[0], a);
+        assert_eq!(line[line.len() - 1], b);
+
+        // consecutive cells are neighbours, sharing an edge unless stepping
+        // across a corner
+        let line = calculate_supercover_line(a, b, CornerPolicy::Diagonal);
+        for pair in line.windows(2) {
+          let (dx, dy) = ((pair[1].x - pair[0].x).abs(), (pair[1].y - pair[0].y).abs());
+          assert!(dx <= 1 && dy <= 1 && dx + dy >= 1);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn full_range_without_overflow() {
+    let mut iter = super::SupercoverIter::new(
+      Point::new(i8::MIN, i8::MAX),
+      Point::new(i8::MAX, i8::MIN),
+      CornerPolicy::Diagonal
+    );
+    assert_eq!(iter.next(), Some(Point::new(-128, 127)));
+    assert_eq!(iter.next(), Some(Point::new(-127, 126)));
+    assert_eq!(iter.last(), Some(Point::new(127, -128)));
+  }
+}
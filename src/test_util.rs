@@ -0,0 +1,20 @@
+// helpers shared by the tests of the rasterizers
+
+use core::fmt::Debug;
+
+use crate::bresenham::Point;
+
+// the pixels as (x, y) pairs, in order
+pub(crate) fn sorted<T: Copy + Ord>(pixels: &[Point<T>]) -> Vec<(T, T)> {
+  let mut pixels: Vec<_> = pixels.iter().map(|p| (p.x, p.y)).collect();
+  pixels.sort();
+  pixels
+}
+
+// like sorted, checking that no pixel is there twice
+pub(crate) fn sorted_distinct<T: Copy + Ord + Debug>(pixels: &[Point<T>]) -> Vec<(T, T)> {
+  let mut distinct = sorted(pixels);
+  distinct.dedup();
+  assert_eq!(distinct.len(), pixels.len(), "repeated pixels in {:?}", pixels);
+  distinct
+}
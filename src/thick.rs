@@ -0,0 +1,316 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{ceil_div, floor_div, LineRSInt, LineRSWide, Point};
+
+/// How a thick line ends at each endpoint.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineCap {
+  /// The line stops square across its endpoints.
+  Butt,
+  /// The line carries on for half its width past each endpoint.
+  Square,
+}
+
+/// Lazily yields every pixel covered by a line of the given `width` from
+/// `p1` to `p2`, each exactly once.
+///
+/// Like Murphy's algorithm, the line is swept along its major axis and the
+/// run of pixels in each column (or row, for steep lines) is bounded by the
+/// exact perpendicular distance from the ideal line, so neighbouring runs
+/// neither overlap nor leave gaps. Runs are yielded in order of increasing
+/// major coordinate, whichever way round the endpoints are given.
+///
+/// A pixel is covered when its centre lies inside the rectangle of the
+/// line. A rectangle edge passing exactly through pixel centres counts on
+/// one side only, so a horizontal or vertical line of width `n` is `n`
+/// pixels wide. Any width of at least 1 covers the endpoints themselves,
+/// as in `calculate_line`, and a width of 0 covers nothing.
+///
+/// The arithmetic is done in `LineRSInt::Wide` and needs `width + 2`
+/// squared times the squared length of the line to fit there; a longer or
+/// wider line draws nothing. Covered pixels that fall outside the range of
+/// `T` are skipped. A zero-length line is a square about `width` pixels
+/// across with square caps, and draws nothing with butt caps.
+#[derive(Debug, Clone)]
+pub struct ThickLineIter<T: LineRSInt> {
+  swap_axes: bool,
+  // origin and direction in (possibly axis-swapped) coordinates, walked
+  // with u along the major axis and v along the minor axis
+  a: T::Wide,
+  b: T::Wide,
+  da: T::Wide,
+  db: T::Wide,
+  // inclusive bounds on 2 * (u * db - v * da), the scaled distance across
+  across_lo: T::Wide,
+  across_hi: T::Wide,
+  // inclusive bounds on 2 * (u * da + v * db), the scaled distance along
+  along_lo: T::Wide,
+  along_hi: T::Wide,
+  u: T::Wide,
+  u_end: T::Wide,
+  v: T::Wide,
+  v_end: T::Wide,
+  done: bool,
+}
+
+impl<T: LineRSInt> ThickLineIter<T> {
+  pub fn new(p1: Point<T>, p2: Point<T>, width: u32, cap: LineCap) -> ThickLineIter<T> {
+    let zero = T::Wide::from(0);
+    let one = T::Wide::from(1);
+    let two = T::Wide::from(2);
+    let widen = |p: Point<T>| p.x.line_rs_widen().zip(p.y.line_rs_widen());
+    // a line with an end out of range of Wide, or too big for it, is empty
+    let ends = widen(p1).zip(widen(p2)).filter(|&(p, q)| room_needed(p, q, width).is_some());
+    let ((x1, y1), (x2, y2)) = ends.unwrap_or(((zero, zero), (zero, zero)));
+    let (mut da, mut db) = (x2 - x1, y2 - y1);
+    let (mut a, mut b) = (x1, y1);
+    let (mut a2, mut b2) = (x2, y2);
+
+    // walk the longer axis, as calculate_line does
+    let abs = |v: T::Wide| if v < zero { -v } else { v };
+    let swap_axes = abs(da) < abs(db);
+    if swap_axes {
+      core::mem::swap(&mut da, &mut db);
+      core::mem::swap(&mut a, &mut b);
+      core::mem::swap(&mut a2, &mut b2);
+    }
+    // start from whichever end has the lower major coordinate, so the
+    // covered pixels don't depend on which way the line was given
+    if da < zero {
+      a = a2;
+      b = b2;
+      da = -da;
+      db = -db;
+    }
+
+    let width = T::Wide::from(width);
+    let length_squared = da * da + db * db;
+    // with no direction to go on, pretend the line runs along the major
+    // axis so the square cap still has a shape
+    let (da, db, scale) = if da == zero {
+      (one, zero, one)
+    } else {
+      (da, db, length_squared)
+    };
+    // the line's half-width is width * |d| / 2, and |d| is usually
+    // irrational. every distance compared against it is an integer, so
+    // comparing against floor(width * |d|) is exact.
+    let reach = (width * width * scale).line_rs_isqrt();
+    let exact = reach * reach == width * width * scale;
+    let (along_lo, along_hi) = match cap {
+      LineCap::Butt => (zero, two * length_squared),
+      LineCap::Square => (-reach, two * length_squared + reach),
+    };
+
+    ThickLineIter {
+      swap_axes,
+      a,
+      b,
+      da,
+      db,
+      across_lo: if exact { one - reach } else { -reach },
+      across_hi: reach,
+      along_lo,
+      along_hi,
+      // the corners of the line's rectangle are never more than `width`
+      // past either endpoint along the major axis
+      u: -width - one,
+      u_end: da + width,
+      v: one,
+      v_end: zero,
+      done: ends.is_none() || along_lo > along_hi || (cap == LineCap::Butt && length_squared == zero),
+    }
+  }
+
+  // the covered minor offsets in column u, as an inclusive range
+  fn column(&self, u: T::Wide) -> (T::Wide, T::Wide) {
+    let zero = T::Wide::from(0);
+    let two = T::Wide::from(2);
+    let (da, db) = (self.da, self.db);
+
+    // across_lo <= 2 * (u * db - v * da) <= across_hi
+    let mut lo = ceil_div(two * u * db - self.across_hi, two * da);
+    let mut hi = floor_div(two * u * db - self.across_lo, two * da);
+
+    // along_lo <= 2 * (u * da + v * db) <= along_hi
+    let along = two * u * da;
+    if db == zero {
+      if along < self.along_lo || along > self.along_hi {
+        return (T::Wide::from(1), zero);
+      }
+    } else {
+      let (near, far) = if db > zero {
+        (self.along_lo, self.along_hi)
+      } else {
+        (self.along_hi, self.along_lo)
+      };
+      lo = lo.max(ceil_div(near - along, two * db));
+      hi = hi.min(floor_div(far - along, two * db));
+    }
+    (lo, hi)
+  }
+}
+
+// (width + 2)^2 times the squared length of the line from (x1, y1) to
+// (x2, y2), which bounds every value ThickLineIter works out, or None when
+// it overflows
+fn room_needed<W: LineRSWide>((x1, y1): (W, W), (x2, y2): (W, W), width: u32) -> Option<W> {
+  let square = |v: W| v.line_rs_checked_mul(v);
+  let length_squared = square(x2.line_rs_checked_sub(x1)?)?.line_rs_checked_add(square(y2.line_rs_checked_sub(y1)?)?)?;
+  let margin = W::from(width) + W::from(2);
+  square(margin)?.line_rs_checked_mul(length_squared.max(W::from(1)))
+}
+
+impl<T: LineRSInt> Iterator for ThickLineIter<T> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    let one = T::Wide::from(1);
+    while !self.done {
+      if self.v > self.v_end {
+        if self.u >= self.u_end {
+          self.done = true;
+          break;
+        }
+        self.u = self.u + one;
+        let (v, v_end) = self.column(self.u);
+        self.v = v;
+        self.v_end = v_end;
+        continue;
+      }
+
+      // with no headroom in Wide, the pixel can be past its range as well
+      let a = self.a.line_rs_checked_add(self.u);
+      let b = self.b.line_rs_checked_add(self.v);
+      self.v = self.v + one;
+      let (x, y) = if self.swap_axes { (b, a) } else { (a, b) };
+      if let (Some(x), Some(y)) = (x.and_then(T::line_rs_narrow), y.and_then(T::line_rs_narrow)) {
+        return Some(Point { x, y });
+      }
+    }
+    None
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for ThickLineIter<T> {}
+
+/// All the pixels of `ThickLineIter::new(p1, p2, width, cap)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_thick_line<T: LineRSInt>(
+  p1: Point<T>,
+  p2: Point<T>,
+  width: u32,
+  cap: LineCap,
+) -> Vec<Point<T>> {
+  ThickLineIter::new(p1, p2, width, cap).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::calculate_thick_line;
+  use super::LineCap;
+  use super::ThickLineIter;
+  use crate::bresenham::calculate_line;
+  use crate::bresenham::Point;
+  use crate::test_util::{sorted, sorted_distinct};
+
+  #[test]
+  fn horizontal_line_is_width_rows_tall() {
+    let line = calculate_thick_line(Point::new(0, 0), Point::new(4, 0), 3, LineCap::Butt);
+    let mut expected = vec![];
+    for x in 0..=4 {
+      for y in -1..=1 {
+        expected.push((x, y));
+      }
+    }
+    assert_eq!(sorted(&line), expected);
+
+    let line = calculate_thick_line(Point::new(0, 0), Point::new(4, 0), 2, LineCap::Butt);
+    assert_eq!(line.len(), 10);
+    let reversed = calculate_thick_line(Point::new(4, 0), Point::new(0, 0), 2, LineCap::Butt);
+    assert_eq!(sorted(&reversed), sorted(&line));
+
+    // square caps add a pixel at each end for a width of 3
+    let line = calculate_thick_line(Point::new(0, 0), Point::new(4, 0), 3, LineCap::Square);
+    assert_eq!(line.len(), 21);
+    assert!(line.contains(&Point::new(-1, 1)));
+    assert!(line.contains(&Point::new(5, -1)));
+
+    // a width of 0 covers nothing, not even the endpoints
+    assert_eq!(calculate_thick_line(Point::new(0, 0), Point::new(4, 0), 0, LineCap::Square), vec![]);
+    assert_eq!(calculate_thick_line(Point::new(0, 0), Point::new(4, 3), 0, LineCap::Butt), vec![]);
+  }
+
+  #[test]
+  fn pixels_are_unique_and_cover_the_thin_line() {
+    let ends = [(9, 2), (-7, 5), (3, -8), (-4, -4), (0, 6), (1, 0), (4, 3)];
+    for &(x1, y1) in &ends {
+      for &(x2, y2) in &ends {
+        for width in 1..=5 {
+          for &cap in &[LineCap::Butt, LineCap::Square] {
+            let p1 = Point::new(x1, y1);
+            let p2 = Point::new(x2, y2);
+            let line = calculate_thick_line(p1, p2, width, cap);
+            let pixels = sorted_distinct(&line);
+            if width >= 2 && p1 != p2 {
+              for p in calculate_line(p1, p2) {
+                assert!(line.contains(&p), "{:?} missing from {:?}-{:?} w{}", p, p1, p2, width);
+              }
+            }
+            // reversing the line covers the same pixels
+            assert_eq!(sorted(&calculate_thick_line(p2, p1, width, cap)), pixels);
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn diagonal_butt_ends_are_perpendicular() {
+    let line = calculate_thick_line(Point::new(0, 0), Point::new(6, 6), 3, LineCap::Butt);
+    // nothing reaches behind the perpendicular through either endpoint
+    assert!(line.iter().all(|p| p.x + p.y >= 0 && p.x + p.y <= 12));
+    assert!(line.contains(&Point::new(-1, 1)));
+    assert!(line.contains(&Point::new(7, 5)));
+    assert!(!line.contains(&Point::new(-1, 0)));
+  }
+
+  #[test]
+  fn out_of_range_pixels_are_skipped() {
+    let line = calculate_thick_line(Point::new(0u8, 0), Point::new(10, 0), 3, LineCap::Square);
+    assert!(line.iter().all(|p| p.x <= 11 && p.y <= 1));
+    assert_eq!(line.len(), 12 * 2);
+  }
+
+  #[test]
+  fn wide_line_across_the_full_range() {
+    // far wider than the range of i16, so most of each column is skipped
+    let mut line = ThickLineIter::new(Point::new(i16::MIN, 0), Point::new(i16::MAX, 0), 100_000, LineCap::Butt);
+    assert_eq!(line.next(), Some(Point::new(i16::MIN, i16::MIN)));
+    assert_eq!(line.nth(65535), Some(Point::new(i16::MIN + 1, i16::MIN)));
+  }
+
+  #[test]
+  fn too_big_for_wide_draws_nothing() {
+    // 11^2 * 2^120 fits in i128 but 12^2 * 2^120 doesn't
+    let (p1, p2) = (Point::new(0i64, 0), Point::new(1 << 60, 0));
+    assert_eq!(ThickLineIter::new(p1, p2, 9, LineCap::Butt).next(), Some(Point::new(0, -4)));
+    assert_eq!(ThickLineIter::new(p1, p2, 10, LineCap::Butt).next(), None);
+    let (p1, p2) = (Point::new(i64::MIN, 0), Point::new(i64::MAX, 0));
+    assert_eq!(ThickLineIter::new(p1, p2, 3, LineCap::Butt).next(), None);
+
+    // i128 is its own Wide, so pixels past its end are skipped
+    let line = calculate_thick_line(Point::new(i128::MAX - 2, 0), Point::new(i128::MAX, 0), 3, LineCap::Square);
+    let pixels: Vec<_> = sorted(&line).into_iter().map(|(x, y)| (i128::MAX - x, y)).collect();
+    assert_eq!(pixels.len(), 12);
+    assert!(pixels.iter().all(|&(x, y)| x <= 3 && (-1..=1).contains(&y)));
+
+    // and u128 coordinates past i128::MAX don't fit in it at all
+    let (near, far) = (Point::new(10u128, 5), Point::new(u128::MAX - 5, 5));
+    assert_eq!(calculate_thick_line(near, far, 3, LineCap::Butt), vec![]);
+    assert_eq!(calculate_thick_line(far, far, 3, LineCap::Square), vec![]);
+    assert_eq!(calculate_thick_line(near, Point::new(14, 5), 3, LineCap::Butt).len(), 15);
+  }
+}
@@ -0,0 +1,284 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{ceil_div, floor_div, LineRSInt, LineRSWide, Point, Span};
+
+type WidePoint<T> = (<T as LineRSInt>::Wide, <T as LineRSInt>::Wide);
+
+// one edge of a triangle, from (ax, ay) along (dx, dy). the triangle's
+// vertices are ordered so that dx * (y - ay) - dy * (x - ax) is positive
+// on the inside of every edge.
+#[derive(Debug, Clone)]
+struct Edge<T: LineRSInt> {
+  ax: T::Wide,
+  ay: T::Wide,
+  dx: T::Wide,
+  dy: T::Wide,
+  // the least value of the edge function a covered pixel centre may have:
+  // 0 on top and left edges, so that centres on them count, and 1 on the
+  // others
+  threshold: T::Wide,
+}
+
+impl<T: LineRSInt> Edge<T> {
+  fn new((ax, ay): (T::Wide, T::Wide), (bx, by): (T::Wide, T::Wide)) -> Edge<T> {
+    let zero = T::Wide::from(0);
+    let (dx, dy) = (bx - ax, by - ay);
+    // with y growing downwards, a top edge is horizontal with the inside
+    // below it, and a left edge has the inside to its right
+    let top_left = dy < zero || (dy == zero && dx > zero);
+    Edge { ax, ay, dx, dy, threshold: if top_left { zero } else { T::Wide::from(1) } }
+  }
+
+  // narrows the covered columns (lo, hi) of row y to this edge's side
+  fn clip_row(&self, y: T::Wide, (lo, hi): (T::Wide, T::Wide)) -> (T::Wide, T::Wide) {
+    let zero = T::Wide::from(0);
+    // dx * (y - ay) - dy * (x - ax) >= threshold, so
+    // -dy * (x - ax) >= threshold - dx * (y - ay)
+    let rest = self.threshold - self.dx * (y - self.ay);
+    if self.dy < zero {
+      (lo.max(self.ax + ceil_div(rest, -self.dy)), hi)
+    } else if self.dy > zero {
+      (lo, hi.min(self.ax + floor_div(-rest, self.dy)))
+    } else if rest <= zero {
+      (lo, hi)
+    } else {
+      (hi + T::Wide::from(1), hi)
+    }
+  }
+}
+
+/// Lazily yields the pixels covered by the triangle with vertices `a`, `b`
+/// and `c`, in either winding, as horizontal spans from the top row down.
+/// Rows with nothing covered are left out.
+///
+/// A pixel is covered when its centre is inside the triangle. Centres
+/// exactly on an edge follow the top-left rule used by GPUs: with y
+/// growing downwards, they are covered on top edges (horizontal edges with
+/// the triangle below) and left edges, and not on the others. Triangles
+/// that share an edge therefore cover every pixel along it exactly once,
+/// so a mesh of triangles neither overlaps nor leaves gaps. A triangle
+/// with no area covers nothing.
+///
+/// The arithmetic is done in `LineRSInt::Wide`, relative to the top left
+/// corner of the triangle's bounding box, and needs twice the area of the
+/// box to fit there. A bigger triangle covers nothing.
+#[derive(Debug, Clone)]
+pub struct TriangleSpanIter<T: LineRSInt> {
+  edges: [Edge<T>; 3],
+  // the corner of the bounding box, and its far side and bottom row
+  // relative to it
+  origin: (T::Wide, T::Wide),
+  x_max: T::Wide,
+  y: T::Wide,
+  y_max: T::Wide,
+}
+
+impl<T: LineRSInt> TriangleSpanIter<T> {
+  pub fn new(a: Point<T>, b: Point<T>, c: Point<T>) -> TriangleSpanIter<T> {
+    let zero = T::Wide::from(0);
+    // a vertex out of range of Wide, or a triangle too big for it, is left
+    // with no area
+    let (origin, [a, b, c]) = placed([a, b, c]).unwrap_or(((zero, zero), [(zero, zero); 3]));
+    let ((ax, ay), (bx, by), (cx, cy)) = (a, b, c);
+    // each product is at most the area of the bounding box
+    let area = (bx - ax) * (cy - ay) - (by - ay) * (cx - ax);
+    let (b, c) = if area < zero { (c, b) } else { (b, c) };
+    TriangleSpanIter {
+      edges: [Edge::new(a, b), Edge::new(b, c), Edge::new(c, a)],
+      origin,
+      x_max: ax.max(bx).max(cx),
+      y: zero,
+      // with no area there is nothing to cover
+      y_max: if area == zero { -T::Wide::from(1) } else { ay.max(by).max(cy) },
+    }
+  }
+}
+
+// the top left corner of the bounding box of the vertices, and the
+// vertices relative to it, when twice the area of the box fits in Wide
+fn placed<T: LineRSInt>(vertices: [Point<T>; 3]) -> Option<(WidePoint<T>, [WidePoint<T>; 3])> {
+  let zero = T::Wide::from(0);
+  let mut placed = [(zero, zero); 3];
+  for (wide, p) in placed.iter_mut().zip(&vertices) {
+    *wide = (p.x.line_rs_widen()?, p.y.line_rs_widen()?);
+  }
+  let x0 = placed.iter().map(|p| p.0).min()?;
+  let y0 = placed.iter().map(|p| p.1).min()?;
+  for wide in &mut placed {
+    *wide = (wide.0.line_rs_checked_sub(x0)?, wide.1.line_rs_checked_sub(y0)?);
+  }
+  let width = placed.iter().map(|p| p.0).max()?;
+  let height = placed.iter().map(|p| p.1).max()?;
+  T::Wide::from(2).line_rs_checked_mul(width)?.line_rs_checked_mul(height)?;
+  Some(((x0, y0), placed))
+}
+
+impl<T: LineRSInt> Iterator for TriangleSpanIter<T> {
+  type Item = Span<T>;
+
+  fn next(&mut self) -> Option<Span<T>> {
+    while self.y <= self.y_max {
+      let y = self.y;
+      self.y = self.y + T::Wide::from(1);
+      let (lo, hi) = self.edges.iter().fold((T::Wide::from(0), self.x_max), |row, edge| edge.clip_row(y, row));
+      if lo > hi {
+        continue;
+      }
+      // covered pixels lie within the bounding box of the vertices, so
+      // they are always in range
+      let (x0, y0) = self.origin;
+      let (y, lo, hi) = (T::line_rs_narrow(y0 + y), T::line_rs_narrow(x0 + lo), T::line_rs_narrow(x0 + hi));
+      if let (Some(y), Some(lo), Some(hi)) = (y, lo, hi) {
+        return Some(Span { y, x_start: lo, x_end: hi });
+      }
+    }
+    None
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for TriangleSpanIter<T> {}
+
+/// Lazily yields the pixels of `TriangleSpanIter::new(a, b, c)` one at a
+/// time, row by row from the top and left to right along each row.
+#[derive(Debug, Clone)]
+pub struct TriangleIter<T: LineRSInt> {
+  spans: TriangleSpanIter<T>,
+  // the rest of the current span
+  span: Option<Span<T>>,
+}
+
+impl<T: LineRSInt> TriangleIter<T> {
+  pub fn new(a: Point<T>, b: Point<T>, c: Point<T>) -> TriangleIter<T> {
+    TriangleIter { spans: TriangleSpanIter::new(a, b, c), span: None }
+  }
+}
+
+impl<T: LineRSInt> Iterator for TriangleIter<T> {
+  type Item = Point<T>;
+
+  fn next(&mut self) -> Option<Point<T>> {
+    let Span { y, x_start: x, x_end } = match self.span {
+      Some(span) => span,
+      None => self.spans.next()?,
+    };
+    self.span = if x == x_end {
+      None
+    } else {
+      Some(Span { y, x_start: x.line_rs_add_magnitude(T::Magnitude::line_rs_one()), x_end })
+    };
+    Some(Point { x, y })
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for TriangleIter<T> {}
+
+/// All the pixels of `TriangleIter::new(a, b, c)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_triangle<T: LineRSInt>(a: Point<T>, b: Point<T>, c: Point<T>) -> Vec<Point<T>> {
+  TriangleIter::new(a, b, c).collect()
+}
+
+/// All the spans of `TriangleSpanIter::new(a, b, c)`.
+#[cfg(feature = "alloc")]
+pub fn calculate_filled_triangle<T: LineRSInt>(a: Point<T>, b: Point<T>, c: Point<T>) -> Vec<Span<T>> {
+  TriangleSpanIter::new(a, b, c).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::{calculate_filled_triangle, calculate_triangle};
+  use crate::bresenham::{Point, Span};
+  use crate::test_util::sorted_distinct;
+
+  #[test]
+  fn right_triangle() {
+    // the top and left edges are covered, the hypotenuse is not
+    let spans = calculate_filled_triangle(Point::new(0, 0), Point::new(4, 0), Point::new(0, 4));
+    assert_eq!(spans, vec![Span::new(0, 0, 3), Span::new(1, 0, 2), Span::new(2, 0, 1), Span::new(3, 0, 0)]);
+    let flipped = calculate_filled_triangle(Point::new(4, 4), Point::new(4, 0), Point::new(0, 4));
+    assert_eq!(flipped, vec![Span::new(1, 3, 3), Span::new(2, 2, 3), Span::new(3, 1, 3)]);
+
+    // every winding and starting vertex gives the same triangle
+    let (a, b, c) = (Point::new(-3, 1), Point::new(7, -5), Point::new(2, 9));
+    let expected = calculate_filled_triangle(a, b, c);
+    for &(p, q, r) in &[(a, c, b), (b, a, c), (b, c, a), (c, a, b), (c, b, a)] {
+      assert_eq!(calculate_filled_triangle(p, q, r), expected);
+    }
+    let pixels = calculate_triangle(a, b, c);
+    let mut expanded = vec![];
+    for &Span { y, x_start, x_end } in &expected {
+      expanded.extend((x_start..=x_end).map(|x| Point::new(x, y)));
+    }
+    assert_eq!(pixels, expanded);
+
+    assert_eq!(calculate_filled_triangle(Point::new(0, 0), Point::new(3, 3), Point::new(6, 6)), vec![]);
+    assert_eq!(calculate_triangle(Point::new(1u8, 1), Point::new(1, 1), Point::new(1, 1)), vec![]);
+  }
+
+  #[test]
+  fn shared_edge_meshes() {
+    // a jittered grid of quads, each split along a diagonal, covers every
+    // pixel of the square it fills exactly once
+    let (cells, size) = (5, 8);
+    let vertex = |i: i32, j: i32| {
+      let jitter = |k: i32| if i == 0 || j == 0 || i == cells || j == cells { 0 } else { (i * 7 + j * 13 + k) % 5 - 2 };
+      Point::new(i * size + jitter(0), j * size + jitter(3))
+    };
+    let mut count = vec![vec![0; 50]; 50];
+    for i in 0..cells {
+      for j in 0..cells {
+        let (p00, p10, p01, p11) = (vertex(i, j), vertex(i + 1, j), vertex(i, j + 1), vertex(i + 1, j + 1));
+        let triangles = if (i + j) % 2 == 0 {
+          [(p00, p10, p11), (p00, p11, p01)]
+        } else {
+          [(p10, p01, p00), (p10, p11, p01)]
+        };
+        for &(a, b, c) in &triangles {
+          for p in calculate_triangle(a, b, c) {
+            count[(p.y + 5) as usize][(p.x + 5) as usize] += 1;
+          }
+        }
+      }
+    }
+    for y in -5..45 {
+      for x in -5..45 {
+        let inside = (0..cells * size).contains(&x) && (0..cells * size).contains(&y);
+        assert_eq!(count[(y + 5) as usize][(x + 5) as usize], inside as i32, "({}, {})", x, y);
+      }
+    }
+
+    // a fan about a point shared by many thin triangles
+    let rim = [(10, 0), (7, 7), (0, 10), (-7, 7), (-10, 0), (-7, -7), (0, -10), (7, -7), (10, 0)];
+    let mut fan = vec![];
+    for pair in rim.windows(2) {
+      fan.extend(calculate_triangle(Point::new(0, 0), Point::new(pair[0].0, pair[0].1), Point::new(pair[1].0, pair[1].1)));
+    }
+    sorted_distinct(&fan);
+    assert!(fan.contains(&Point::new(0, 0)));
+  }
+
+  #[test]
+  fn limits_of_wide() {
+    use super::TriangleSpanIter;
+    // twice the area of the bounding box must fit in i128
+    let (min, max) = (i64::MIN, i64::MAX);
+    let full = TriangleSpanIter::new(Point::new(min, min), Point::new(max, min), Point::new(0, max));
+    assert_eq!(full.count(), 0);
+    let side = 1i64 << 62;
+    let mut big = TriangleSpanIter::new(Point::new(0, 0), Point::new(side, 0), Point::new(0, side));
+    assert_eq!(big.next(), Some(Span::new(0, 0, side - 1)));
+    assert_eq!(big.next(), Some(Span::new(1, 0, side - 2)));
+    // however far from the origin it is
+    let small = calculate_filled_triangle(Point::new(0i128, 2), Point::new(7, 0), Point::new(3, 6));
+    for &(x, y) in &[(i128::MAX - 7, i128::MAX - 6), (i128::MIN, i128::MIN), (i128::MIN, i128::MAX - 6)] {
+      let moved: Vec<_> = small.iter().map(|s| Span::new(s.y + y, s.x_start + x, s.x_end + x)).collect();
+      let (a, b, c) = (Point::new(x, y + 2), Point::new(x + 7, y), Point::new(x + 3, y + 6));
+      assert_eq!(calculate_filled_triangle(a, b, c), moved);
+    }
+    let (a, b) = (Point::new(0u128, 0), Point::new(4, 0));
+    assert_eq!(calculate_filled_triangle(a, b, Point::new(u128::MAX, 4)), vec![]);
+    assert_eq!(calculate_triangle(a, b, Point::new(0, 4)).len(), 10);
+  }
+}
@@ -0,0 +1,178 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::bresenham::{offset, LineRSInt, LineRSUint, Point, Sign, SignedInt};
+
+/// Coverage of a pixel that lies entirely on the line.
+pub const FULL_COVERAGE: u8 = 255;
+
+/// Lazily yields the pixels of an anti-aliased line from `p1` to `p2`
+/// (Xiaolin Wu's algorithm), each with how much of it the line covers, from
+/// 1 to `FULL_COVERAGE`.
+///
+/// For every step along the major axis the ideal line falls between two
+/// pixels on the minor axis, which share the coverage in proportion to how
+/// close the line passes. Both pixels of a step are yielded before the next
+/// step, the one nearer `p1`'s minor coordinate first; a pixel the line
+/// passes straight through is yielded alone with full coverage.
+#[derive(Debug, Clone)]
+pub struct WuIter<T: LineRSInt> {
+  x_magnitude: T::Magnitude,
+  y_magnitude: T::Magnitude,
+  swap_axes: bool,
+  x: T,
+  y: T,
+  x_sign: Sign,
+  y_sign: Sign,
+  // the ideal line is `remainder / x_magnitude` of a pixel past `y`
+  remainder: T::Magnitude,
+  // steps left after the current one
+  remaining: T::Magnitude,
+  pending: Option<(Point<T>, u8)>,
+  done: bool,
+}
+
+impl<T: LineRSInt> WuIter<T> {
+  pub fn new(p1: Point<T>, p2: Point<T>) -> WuIter<T> {
+    let mut x_diff = SignedInt::diff_of(p2.x, p1.x);
+    let mut y_diff = SignedInt::diff_of(p2.y, p1.y);
+
+    // same octant folding as the bresenham line: walk the longer axis
+    let swap_axes = x_diff.magnitude < y_diff.magnitude;
+    let (mut x, mut y) = (p1.x, p1.y);
+    if swap_axes {
+      core::mem::swap(&mut x_diff, &mut y_diff);
+      core::mem::swap(&mut x, &mut y);
+    }
+
+    WuIter {
+      x_magnitude: x_diff.magnitude,
+      y_magnitude: y_diff.magnitude,
+      swap_axes,
+      x,
+      y,
+      x_sign: x_diff.sign,
+      y_sign: y_diff.sign,
+      remainder: T::Magnitude::line_rs_zero(),
+      remaining: x_diff.magnitude,
+      pending: None,
+      done: false,
+    }
+  }
+
+  fn point_of(&self, x: T, y: T) -> Point<T> {
+    if self.swap_axes {
+      Point { x: y, y: x }
+    } else {
+      Point { x, y }
+    }
+  }
+}
+
+impl<T: LineRSInt> Iterator for WuIter<T> {
+  type Item = (Point<T>, u8);
+
+  fn next(&mut self) -> Option<(Point<T>, u8)> {
+    if let Some(pixel) = self.pending.take() {
+      return Some(pixel);
+    }
+    if self.done {
+      return None;
+    }
+
+    let zero = T::Magnitude::line_rs_zero();
+    let one = T::Magnitude::line_rs_one();
+    // remainder < x_magnitude, so the far pixel's share is below full
+    let far = if self.remainder == zero {
+      0
+    } else {
+      let full = T::Magnitude::from(FULL_COVERAGE);
+      self.remainder.line_rs_mul_div(full, self.x_magnitude).0.line_rs_to_usize() as u8
+    };
+    let pixel = (self.point_of(self.x, self.y), FULL_COVERAGE - far);
+    if far > 0 {
+      let y = offset(self.y, self.y_sign, one);
+      self.pending = Some((self.point_of(self.x, y), far));
+    }
+
+    if self.remaining == zero {
+      self.done = true;
+    } else {
+      self.remaining = self.remaining - one;
+      self.x = offset(self.x, self.x_sign, one);
+      // remainder + y_magnitude >= x_magnitude, without the sum overflowing
+      let gap = self.x_magnitude - self.y_magnitude;
+      if self.remainder >= gap {
+        self.remainder = self.remainder - gap;
+        self.y = offset(self.y, self.y_sign, one);
+      } else {
+        self.remainder = self.remainder + self.y_magnitude;
+      }
+    }
+    Some(pixel)
+  }
+}
+
+impl<T: LineRSInt> FusedIterator for WuIter<T> {}
+
+/// All the pixels of `WuIter::new(p1, p2)` with their coverage.
+#[cfg(feature = "alloc")]
+pub fn calculate_wu_line<T: LineRSInt>(
+  p1: Point<T>,
+  p2: Point<T>,
+) -> Vec<(Point<T>, u8)> {
+  WuIter::new(p1, p2).collect()
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::calculate_wu_line;
+  use super::FULL_COVERAGE;
+  use crate::bresenham::Point;
+
+  #[test]
+  fn axis_aligned_and_diagonal_are_solid() {
+    let line = calculate_wu_line(Point::new(2, 5), Point::new(2, 1));
+    assert_eq!(line.len(), 5);
+    assert!(line.iter().all(|&(_, coverage)| coverage == FULL_COVERAGE));
+    assert_eq!(line[1], (Point::new(2, 4), FULL_COVERAGE));
+
+    let line = calculate_wu_line(Point::new(0u8, 255), Point::new(255, 0));
+    assert_eq!(line.len(), 256);
+    assert!(line.iter().all(|&(p, coverage)| p.x == 255 - p.y && coverage == FULL_COVERAGE));
+  }
+
+  #[test]
+  fn shallow_line_shares_coverage() {
+    let line = calculate_wu_line(Point::new(0, 0), Point::new(4, 1));
+    let expected = vec![
+      (Point::new(0, 0), 255),
+      (Point::new(1, 0), 192),
+      (Point::new(1, 1), 63),
+      (Point::new(2, 0), 128),
+      (Point::new(2, 1), 127),
+      (Point::new(3, 0), 64),
+      (Point::new(3, 1), 191),
+      (Point::new(4, 1), 255),
+    ];
+    assert_eq!(line, expected);
+  }
+
+  #[test]
+  fn steep_line_swaps_axes() {
+    // the same line as above, mirrored and walked downwards
+    let line = calculate_wu_line(Point::new(0i64, 0), Point::new(-1, -4));
+    let expected = vec![
+      (Point::new(0, 0), 255),
+      (Point::new(0, -1), 192),
+      (Point::new(-1, -1), 63),
+      (Point::new(0, -2), 128),
+      (Point::new(-1, -2), 127),
+      (Point::new(0, -3), 64),
+      (Point::new(-1, -3), 191),
+      (Point::new(-1, -4), 255),
+    ];
+    assert_eq!(line, expected);
+  }
+}